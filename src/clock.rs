@@ -2,15 +2,44 @@ use crate::lowlevel::clock::{
     clock_adjtime, clock_gettime, clock_nanosleep, clock_settime, clockid_t, TimeSpec, Timeval,
     TimexRaw, CLOCK_BOOTTIME, CLOCK_BOOTTIME_ALARM, CLOCK_MONOTONIC, CLOCK_MONOTONIC_COARSE,
     CLOCK_MONOTONIC_RAW, CLOCK_PROCESS_CPUTIME_ID, CLOCK_REALTIME, CLOCK_REALTIME_ALARM,
-    CLOCK_REALTIME_COARSE, CLOCK_TAI, CLOCK_THREAD_CPUTIME_ID, TIMER_ABSTIME,
+    CLOCK_REALTIME_COARSE, CLOCK_TAI, CLOCK_THREAD_CPUTIME_ID, TIMER_ABSTIME, TIME_DEL, TIME_ERROR,
+    TIME_INS, TIME_OK, TIME_OOP, TIME_WAIT,
+};
+use crate::lowlevel::clock::{fd_to_clockid, CLOCKFD, CLOCKFD_MASK};
+use crate::lowlevel::io_errno;
+use bitflags::bitflags;
+use std::{
+    fs::{File, OpenOptions},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+    path::Path,
 };
 use syscalls::Errno;
 
+mod cross;
+mod discipline;
+mod format;
+mod leap;
+mod periodic;
+mod precise;
+pub use cross::{cross_timestamp, cross_timestamp_with_samples, ClockConverter, CrossTimestamp};
+pub use discipline::{
+    frequency_ppm, set_est_error, set_frequency_ppm, set_max_error, set_tai_offset, set_tick, slew,
+    step,
+};
+pub use format::Rfc3339;
+pub use leap::{
+    cancel_leap, leap_info, realtime_to_tai, schedule_leap, tai_offset, tai_to_realtime, Leap,
+    LeapInfo, LeapSecond,
+};
+pub use periodic::{CatchUp, Periodic, Tick};
+pub use precise::{precise_sleep_until, PreciseSleeper};
+
 /// The [ClockId] is the identifier of the particular clock on
 /// which to act. A clock may be system-wide and hence visible for
 /// all processes, or per-process if it measures time only within a
 /// single process.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ClockId {
     /// A settable system-wide clock that measures real (i.e., wall-
     /// clock) time.  Setting this clock requires appropriate privi‐
@@ -18,11 +47,13 @@ pub enum ClockId {
     /// system time (e.g., if the system administrator manually
     /// changes the clock), and by the incremental adjustments per‐
     /// formed by adjtime(3) and NTP.
+    #[cfg_attr(feature = "serde", serde(rename = "realtime"))]
     ClockRealtime,
 
     /// (since Linux 3.0; Linux-specific)
     /// Like CLOCK_REALTIME, but not settable.  See timer_create(2)
     /// for further details.
+    #[cfg_attr(feature = "serde", serde(rename = "realtime_alarm"))]
     ClockRealtimeAlarm,
 
     /// (since Linux 2.6.32; Linux-specific)
@@ -31,6 +62,7 @@ pub enum ClockId {
     /// fine-grained timestamps.  Requires per-architecture support,
     /// and probably also architecture support for this flag in the
     /// vdso(7).
+    #[cfg_attr(feature = "serde", serde(rename = "realtime_coarse"))]
     ClockRealtimeCoarse,
 
     /// (since Linux 3.10; Linux-specific)
@@ -40,6 +72,7 @@ pub enum ClockId {
     /// leap seconds as CLOCK_REALTIME does.
     ///
     /// The acronym TAI refers to International Atomic Time.
+    #[cfg_attr(feature = "serde", serde(rename = "tai"))]
     ClockTai,
     /// A nonsettable system-wide clock that represents monotonic time
     /// since—as described by POSIX—"some unspecified point in the
@@ -55,6 +88,7 @@ pub enum ClockId {
     /// consecutive calls will not go backwards, but successive calls
     /// may—depending on the architecture—return identical (not-
     /// increased) time values.
+    #[cfg_attr(feature = "serde", serde(rename = "monotonic"))]
     ClockMonotonic,
 
     ///  (since Linux 2.6.32; Linux-specific)
@@ -62,6 +96,7 @@ pub enum ClockId {
     /// when you need very fast, but not fine-grained timestamps.
     /// Requires per-architecture support, and probably also architec‐
     /// ture support for this flag in the vdso(7).
+    #[cfg_attr(feature = "serde", serde(rename = "monotonic_coarse"))]
     ClockMonotonicCoarse,
 
     ///  (since Linux 2.6.28; Linux-specific)
@@ -69,6 +104,7 @@ pub enum ClockId {
     /// ware-based time that is not subject to NTP adjustments or the
     /// incremental adjustments performed by adjtime(3).  This clock
     /// does not count time that the system is suspended.
+    #[cfg_attr(feature = "serde", serde(rename = "monotonic_raw"))]
     ClockMonotonicRaw,
 
     /// (since Linux 2.6.39; Linux-specific)
@@ -78,20 +114,32 @@ pub enum ClockId {
     /// suspend-aware monotonic clock without having to deal with the
     /// complications of CLOCK_REALTIME, which may have discontinu‐
     /// ities if the time is changed using settimeofday(2) or similar.
+    #[cfg_attr(feature = "serde", serde(rename = "boottime"))]
     ClockBoottime,
     /// (since Linux 3.0; Linux-specific)
     /// Like CLOCK_BOOTTIME.  See timer_create(2) for further details.
+    #[cfg_attr(feature = "serde", serde(rename = "boottime_alarm"))]
     ClockBoottimeAlarm,
     /// (since Linux 2.6.12)
     /// This is a clock that measures CPU time consumed by this
     /// process (i.e., CPU time consumed by all threads in the
     /// process).  On Linux, this clock is not settable.
+    #[cfg_attr(feature = "serde", serde(rename = "process_cputime_id"))]
     ClockProcessCputimeId,
 
     ///  (since Linux 2.6.12)
     /// This is a clock that measures CPU time consumed by this
     /// thread.  On Linux, this clock is not settable.
+    #[cfg_attr(feature = "serde", serde(rename = "thread_cputime_id"))]
     ClockThreadCputimeId,
+
+    /// (since Linux 2.6.39; Linux-specific)
+    /// A dynamic POSIX clock that is backed by a character device such
+    /// as a PTP hardware clock (`/dev/ptp0`). The raw `clockid_t` is
+    /// derived from the file descriptor of the open device, see
+    /// [DynamicClock].
+    #[cfg_attr(feature = "serde", serde(rename = "dynamic"))]
+    Dynamic(clockid_t),
 }
 impl ClockId {
     /// Get the raw `clockid_t`.
@@ -108,6 +156,7 @@ impl ClockId {
             ClockId::ClockBoottimeAlarm => CLOCK_BOOTTIME_ALARM,
             ClockId::ClockProcessCputimeId => CLOCK_PROCESS_CPUTIME_ID,
             ClockId::ClockThreadCputimeId => CLOCK_THREAD_CPUTIME_ID,
+            ClockId::Dynamic(clockid) => *clockid,
         }
     }
     /// Creates [ClockId] from raw `clockid_t`.
@@ -124,11 +173,53 @@ impl ClockId {
             CLOCK_BOOTTIME_ALARM => Some(ClockId::ClockBoottimeAlarm),
             CLOCK_PROCESS_CPUTIME_ID => Some(ClockId::ClockProcessCputimeId),
             CLOCK_THREAD_CPUTIME_ID => Some(ClockId::ClockThreadCputimeId),
+            clockid if clockid < 0 && clockid & CLOCKFD_MASK == CLOCKFD => {
+                Some(ClockId::Dynamic(clockid))
+            }
             _ => None,
         }
     }
 }
 
+/// An open dynamic POSIX clock device, e.g. a PTP hardware clock.
+///
+/// The device is closed on drop, which invalidates its [ClockId].
+#[derive(Debug)]
+pub struct DynamicClock {
+    file: File,
+}
+
+impl DynamicClock {
+    /// Opens the clock device at `path`. The device is opened for reading
+    /// and writing so that the clock can be adjusted.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Errno> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(io_errno)?;
+        Ok(Self { file })
+    }
+
+    /// Returns the [ClockId] of the clock. It is only valid as long as
+    /// the [DynamicClock] is open.
+    pub fn clock_id(&self) -> ClockId {
+        ClockId::Dynamic(fd_to_clockid(self.file.as_raw_fd()))
+    }
+}
+
+impl AsFd for DynamicClock {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl AsRawFd for DynamicClock {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
 /// Retrieve the time of the specified clock [ClockId].
 pub fn get_time(clockid: ClockId) -> Result<TimeSpec, Errno> {
     let mut tp = TimeSpec::zeroed();
@@ -142,12 +233,20 @@ pub fn set_time(clockid: ClockId, tp: TimeSpec) -> Result<(), Errno> {
 
 /// Takes a `Timex` structure, updates kernel parameters from (selected) field
 /// values, and updates the same structure with the current
-/// kernel values.
-pub fn adjust_time(clockid: ClockId, timex: &mut Timex) -> Result<(), Errno> {
+/// kernel values. Returns the [ClockState] of the clock.
+///
+/// Fails with `EINVAL` if `ADJ_STATUS` is set together with any of the
+/// [StatusCodes::READ_ONLY] bits.
+pub fn adjust_time(clockid: ClockId, timex: &mut Timex) -> Result<ClockState, Errno> {
+    if timex.modes.contains(TimexMode::ADJ_STATUS)
+        && timex.status.intersects(StatusCodes::READ_ONLY)
+    {
+        return Err(Errno::EINVAL);
+    }
     let mut timex_raw = TimexRaw::from_timex(timex);
-    unsafe { clock_adjtime(clockid.as_raw(), &raw mut timex_raw).and(Ok(())) }?;
+    let state = unsafe { clock_adjtime(clockid.as_raw(), &raw mut timex_raw) }?;
     *timex = timex_raw.into_timex();
-    Ok(())
+    ClockState::from_raw(state as std::ffi::c_int).ok_or(Errno::EINVAL)
 }
 
 /// The [nanosleep_relative] function shall cause the current thread to be
@@ -180,120 +279,222 @@ pub fn nanosleep_relative_with_remain(clockid: ClockId, ts: TimeSpec) -> Result<
     let mut remaining = TimeSpec::new();
     unsafe { clock_nanosleep(clockid.as_raw(), 0, &ts, &raw mut remaining).and(Ok(remaining)) }
 }
-/// Like [nanosleep_absolute] but returns the amount of time remaining in the
-/// interval (the requested time minus the time actually slept)
-pub fn nanosleep_absolute_with_remain(clockid: ClockId, ts: TimeSpec) -> Result<TimeSpec, Errno> {
-    let mut remaining = TimeSpec::new();
-    unsafe {
-        clock_nanosleep(clockid.as_raw(), TIMER_ABSTIME, &ts, &raw mut remaining).and(Ok(remaining))
-    }
+/// Result of a cancelable sleep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SleepOutcome {
+    /// The requested time has elapsed.
+    Completed,
+    /// The sleep was interrupted by a signal and the cancellation
+    /// predicate returned `true`. Contains the time that was left to sleep.
+    Cancelled(TimeSpec),
 }
 
-/// The modes field determines which parameters, if any, to set.  It is a bit mask
-/// containing a bitwise OR combination of zero or more of the
-/// following bits:
-#[repr(C)]
-#[derive(Debug, Clone, Default)]
-pub struct TimexMode(std::ffi::c_uint);
-impl TimexMode {
-    /// time offset
-    pub const ADJ_OFFSET: std::ffi::c_uint = 0x0001;
-    /// frequency offset
-    pub const ADJ_FREQUENCY: std::ffi::c_uint = 0x0002;
-    /// maximum time error
-    pub const ADJ_MAXERROR: std::ffi::c_uint = 0x0004;
-    /// estimated time error
-    pub const ADJ_ESTERROR: std::ffi::c_uint = 0x0008;
-    /// clock status
-    pub const ADJ_STATUS: std::ffi::c_uint = 0x0010;
-    /// pll time constant
-    pub const ADJ_TIMECONST: std::ffi::c_uint = 0x0020;
-    /// set TAI offset
-    pub const ADJ_TAI: std::ffi::c_uint = 0x0080;
-    /// add 'time' to current time
-    pub const ADJ_SETOFFSET: std::ffi::c_uint = 0x0100;
-    /// select microsecond resolution
-    pub const ADJ_MICRO: std::ffi::c_uint = 0x1000;
-    /// select nanosecond resolution
-    pub const ADJ_NANO: std::ffi::c_uint = 0x2000;
-    /// tick value
-    pub const ADJ_TICK: std::ffi::c_uint = 0x4000;
-    /// Check if `mode` is set.
-    pub fn is_set(&self, mode: std::ffi::c_uint) -> bool {
-        self.0 & mode == mode
+/// Like [nanosleep_relative] but resumes the sleep with the remaining time
+/// when it is interrupted by a signal handler. Returns once the full
+/// interval `ts` has elapsed.
+pub fn nanosleep_relative_restart(clockid: ClockId, ts: TimeSpec) -> Result<(), Errno> {
+    nanosleep_relative_cancelable(clockid, ts, || false).and(Ok(()))
+}
+
+/// Like [nanosleep_absolute] but restarts the sleep to the same deadline
+/// `ts` when it is interrupted by a signal handler. Returns once the clock
+/// has reached `ts`.
+pub fn nanosleep_absolute_restart(clockid: ClockId, ts: TimeSpec) -> Result<(), Errno> {
+    nanosleep_absolute_cancelable(clockid, ts, || false).and(Ok(()))
+}
+
+/// Like [nanosleep_relative_restart] but calls `cancel` after every
+/// interruption by a signal handler. If `cancel` returns `true`, the sleep
+/// ends early with [SleepOutcome::Cancelled] and the remaining time.
+pub fn nanosleep_relative_cancelable(
+    clockid: ClockId,
+    ts: TimeSpec,
+    mut cancel: impl FnMut() -> bool,
+) -> Result<SleepOutcome, Errno> {
+    let mut request = ts;
+    loop {
+        let mut remaining = TimeSpec::new();
+        match unsafe { clock_nanosleep(clockid.as_raw(), 0, &request, &raw mut remaining) } {
+            Ok(_) => return Ok(SleepOutcome::Completed),
+            Err(Errno::EINTR) if cancel() => return Ok(SleepOutcome::Cancelled(remaining)),
+            Err(Errno::EINTR) => request = remaining,
+            Err(err) => return Err(err),
+        }
     }
-    /// Set a `mode`
-    pub fn set(&mut self, mode: std::ffi::c_uint) {
-        self.0 |= mode;
+}
+
+/// Like [nanosleep_absolute_restart] but calls `cancel` after every
+/// interruption by a signal handler. If `cancel` returns `true`, the sleep
+/// ends early with [SleepOutcome::Cancelled] and the time left until `ts`,
+/// measured on the clock [ClockId].
+pub fn nanosleep_absolute_cancelable(
+    clockid: ClockId,
+    ts: TimeSpec,
+    mut cancel: impl FnMut() -> bool,
+) -> Result<SleepOutcome, Errno> {
+    loop {
+        match unsafe {
+            clock_nanosleep(clockid.as_raw(), TIMER_ABSTIME, &ts, core::ptr::null_mut())
+        } {
+            Ok(_) => return Ok(SleepOutcome::Completed),
+            Err(Errno::EINTR) if cancel() => {
+                let remaining = (ts - get_time(clockid)?).max(TimeSpec::zeroed());
+                return Ok(SleepOutcome::Cancelled(remaining));
+            }
+            Err(Errno::EINTR) => continue,
+            Err(err) => return Err(err),
+        }
     }
-    pub(crate) fn as_raw(&self) -> std::ffi::c_uint {
-        self.0
+}
+
+bitflags! {
+    /// The modes field determines which parameters, if any, to set.  It is a bit mask
+    /// containing a bitwise OR combination of zero or more of the
+    /// following bits:
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct TimexMode: std::ffi::c_uint {
+        /// time offset
+        const ADJ_OFFSET = 0x0001;
+        /// frequency offset
+        const ADJ_FREQUENCY = 0x0002;
+        /// maximum time error
+        const ADJ_MAXERROR = 0x0004;
+        /// estimated time error
+        const ADJ_ESTERROR = 0x0008;
+        /// clock status
+        const ADJ_STATUS = 0x0010;
+        /// pll time constant
+        const ADJ_TIMECONST = 0x0020;
+        /// set TAI offset
+        const ADJ_TAI = 0x0080;
+        /// add 'time' to current time
+        const ADJ_SETOFFSET = 0x0100;
+        /// select microsecond resolution
+        const ADJ_MICRO = 0x1000;
+        /// select nanosecond resolution
+        const ADJ_NANO = 0x2000;
+        /// tick value
+        const ADJ_TICK = 0x4000;
+        /// Old-fashioned adjtime(3): gradually adjust the time by the value
+        /// in `offset` (microseconds).
+        const ADJ_OFFSET_SINGLESHOT = 0x8001;
+        /// Return the remaining amount of time to be adjusted after an
+        /// earlier `ADJ_OFFSET_SINGLESHOT` in `offset`. Does not change
+        /// any kernel value.
+        const ADJ_OFFSET_SS_READ = 0xa001;
     }
+}
 
-    pub(crate) fn from_raw(raw: std::ffi::c_uint) -> Self {
-        Self(raw)
+bitflags! {
+    /// The buf.status field is a bit mask that is used to set and/or
+    /// retrieve status bits associated with the NTP implementation.  Some
+    /// bits in the mask are both readable and settable, while others are
+    /// read-only.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct StatusCodes: std::ffi::c_int {
+        /// enable PLL updates (rw)
+        const STA_PLL = 0x0001;
+        /// enable PPS freq discipline (rw)
+        const STA_PPSFREQ = 0x0002;
+        /// enable PPS time discipline (rw)
+        const STA_PPSTIME = 0x0004;
+        /// select frequency-lock mode (rw)
+        const STA_FLL = 0x0008;
+        /// insert leap (rw)
+        const STA_INS = 0x0010;
+        /// delete leap (rw)
+        const STA_DEL = 0x0020;
+        /// clock unsynchronized (rw)
+        const STA_UNSYNC = 0x0040;
+        /// hold frequency (rw)
+        const STA_FREQHOLD = 0x0080;
+        /// PPS signal present (ro)
+        const STA_PPSSIGNAL = 0x0100;
+        /// PPS signal jitter exceeded (ro)
+        const STA_PPSJITTER = 0x0200;
+        /// PPS signal wander exceeded (ro)
+        const STA_PPSWANDER = 0x0400;
+        /// PPS signal calibration error (ro)
+        const STA_PPSERROR = 0x0800;
+        /// clock hardware fault (ro)
+        const STA_CLOCKERR = 0x1000;
+        /// resolution (0 = us, 1 = ns) (ro)
+        const STA_NANO = 0x2000;
+        /// mode (0 = PLL, 1 = FLL) (ro)
+        const STA_MODE = 0x4000;
+        /// clock source (0 = A, 1 = B) (ro)
+        const STA_CLK = 0x8000;
     }
 }
 
-/// The buf.status field is a bit mask that is used to set and/or
-/// retrieve status bits associated with the NTP implementation.  Some
-/// bits in the mask are both readable and settable, while others are
-/// read-only.
-#[repr(C)]
-#[derive(Debug, Clone, Default)]
-pub struct StatusCodes(std::ffi::c_int);
 impl StatusCodes {
-    /// enable PLL updates (rw)
-    pub const STA_PLL: std::ffi::c_int = 0x0001;
-    /// enable PPS freq discipline (rw)
-    pub const STA_PPSFREQ: std::ffi::c_int = 0x0002;
-    /// enable PPS time discipline (rw)
-    pub const STA_PPSTIME: std::ffi::c_int = 0x0004;
-    /// select frequency-lock mode (rw)
-    pub const STA_FLL: std::ffi::c_int = 0x0008;
-    /// insert leap (rw)
-    pub const STA_INS: std::ffi::c_int = 0x0010;
-    /// delete leap (rw)
-    pub const STA_DEL: std::ffi::c_int = 0x0020;
-    /// clock unsynchronized (rw)
-    pub const STA_UNSYNC: std::ffi::c_int = 0x0040;
-    /// hold frequency (rw)
-    pub const STA_FREQHOLD: std::ffi::c_int = 0x0080;
-    /// PPS signal present (ro)
-    pub const STA_PPSSIGNAL: std::ffi::c_int = 0x0100;
-    /// PPS signal jitter exceeded (ro)
-    pub const STA_PPSJITTER: std::ffi::c_int = 0x0200;
-    /// PPS signal wander exceeded (ro)
-    pub const STA_PPSWANDER: std::ffi::c_int = 0x0400;
-    /// PPS signal calibration error (ro)
-    pub const STA_PPSERROR: std::ffi::c_int = 0x0800;
-    /// clock hardware fault (ro)
-    pub const STA_CLOCKERR: std::ffi::c_int = 0x1000;
-    /// resolution (0 = us, 1 = ns) (ro)
-    pub const STA_NANO: std::ffi::c_int = 0x2000;
-    /// mode (0 = PLL, 1 = FLL) (ro)
-    pub const STA_MODE: std::ffi::c_int = 0x4000;
-    /// clock source (0 = A, 1 = B) (ro)
-    pub const STA_CLK: std::ffi::c_int = 0x8000;
-    /// Check if `status_code` is set.
-    pub fn is_set(&self, status_code: std::ffi::c_int) -> bool {
-        self.0 & status_code == status_code
-    }
-    /// Set a `status_code`
-    pub fn set(&mut self, status_code: std::ffi::c_int) {
-        self.0 |= status_code;
-    }
-    pub(crate) fn as_raw(&self) -> std::ffi::c_int {
-        self.0
+    /// All read-only status bits. [adjust_time] rejects these with `ADJ_STATUS`.
+    pub const READ_ONLY: Self = Self::STA_PPSSIGNAL
+        .union(Self::STA_PPSJITTER)
+        .union(Self::STA_PPSWANDER)
+        .union(Self::STA_PPSERROR)
+        .union(Self::STA_CLOCKERR)
+        .union(Self::STA_NANO)
+        .union(Self::STA_MODE)
+        .union(Self::STA_CLK);
+
+    /// Returns only the settable bits. Use this to write back a status
+    /// that was read with [adjust_time].
+    pub const fn writable(self) -> Self {
+        self.difference(Self::READ_ONLY)
     }
+}
 
-    pub(crate) fn from_raw(raw: std::ffi::c_int) -> Self {
-        Self(raw)
+/// The state of the kernel clock as returned by [adjust_time].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockState {
+    /// Clock synchronized, no leap second adjustment pending.
+    Ok,
+    /// Indicates that a leap second will be added at the end of the UTC day.
+    Ins,
+    /// Indicates that a leap second will be deleted at the end of the UTC day.
+    Del,
+    /// Insertion of a leap second is in progress.
+    Oop,
+    /// A leap-second insertion or deletion has been completed. This
+    /// value will be returned until the next `ADJ_STATUS` operation
+    /// clears the `STA_INS` and `STA_DEL` flags.
+    Wait,
+    /// The system clock is not synchronized to a reliable server.
+    Error,
+}
+
+impl ClockState {
+    /// Get the raw clock state.
+    pub const fn as_raw(&self) -> std::ffi::c_int {
+        match self {
+            ClockState::Ok => TIME_OK,
+            ClockState::Ins => TIME_INS,
+            ClockState::Del => TIME_DEL,
+            ClockState::Oop => TIME_OOP,
+            ClockState::Wait => TIME_WAIT,
+            ClockState::Error => TIME_ERROR,
+        }
+    }
+    /// Creates [ClockState] from a raw clock state.
+    pub const fn from_raw(state: std::ffi::c_int) -> Option<Self> {
+        match state {
+            TIME_OK => Some(ClockState::Ok),
+            TIME_INS => Some(ClockState::Ins),
+            TIME_DEL => Some(ClockState::Del),
+            TIME_OOP => Some(ClockState::Oop),
+            TIME_WAIT => Some(ClockState::Wait),
+            TIME_ERROR => Some(ClockState::Error),
+            _ => None,
+        }
     }
 }
 
 /// Required fields to update a clock
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Timex {
     /// Mode selector for `adjtimex`.
     pub modes: TimexMode,
@@ -358,6 +559,43 @@ mod tests {
         adjust_time(ClockId::ClockRealtime, &mut tx).unwrap();
     }
 
+    #[test]
+    fn test_dynamic_clock_id() {
+        let id = fd_to_clockid(3);
+        assert_eq!(ClockId::from_raw(id), Some(ClockId::Dynamic(id)));
+        assert_eq!(ClockId::Dynamic(id).as_raw(), id);
+        // CPU-time clocks of other processes are negative as well.
+        assert_eq!(ClockId::from_raw(-6), None);
+        assert_eq!(
+            DynamicClock::open("/nonexistent/ptp").unwrap_err(),
+            Errno::ENOENT
+        );
+    }
+
+    #[test]
+    fn test_adjust_time_read_only_status() {
+        let mut tx = Timex::default();
+        adjust_time(ClockId::ClockRealtime, &mut tx).unwrap();
+        tx.modes = TimexMode::ADJ_STATUS;
+        tx.status |= StatusCodes::STA_CLOCKERR;
+        assert_eq!(
+            adjust_time(ClockId::ClockRealtime, &mut tx),
+            Err(Errno::EINVAL)
+        );
+        assert!(!tx.status.writable().intersects(StatusCodes::READ_ONLY));
+    }
+
+    #[test]
+    fn test_timex_flags() {
+        assert!(TimexMode::ADJ_OFFSET_SINGLESHOT.contains(TimexMode::ADJ_OFFSET));
+        assert!(TimexMode::ADJ_OFFSET_SS_READ.contains(TimexMode::ADJ_OFFSET_SINGLESHOT));
+        assert_eq!(ClockState::from_raw(TIME_WAIT), Some(ClockState::Wait));
+        assert_eq!(ClockState::from_raw(42), None);
+        for state in [ClockState::Ok, ClockState::Ins, ClockState::Error] {
+            assert_eq!(ClockState::from_raw(state.as_raw()), Some(state));
+        }
+    }
+
     #[test]
     fn test_sleep() {
         nanosleep_relative(
@@ -378,4 +616,76 @@ mod tests {
         .unwrap();
         // assert!(time.tv_sec > 0);
     }
+
+    extern "C" fn ignore_signal(_: std::ffi::c_int) {}
+
+    /// Spawns a thread running `sleep` and interrupts it with `SIGUSR1`
+    /// until it returns.
+    fn interrupt<T: Send + 'static>(sleep: impl FnOnce() -> T + Send + 'static) -> T {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = ignore_signal as *const () as usize;
+            libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut());
+        }
+        let done = Arc::new(AtomicBool::new(false));
+        let done_thread = done.clone();
+        let handle = std::thread::spawn(move || {
+            let ret = sleep();
+            done_thread.store(true, Ordering::SeqCst);
+            ret
+        });
+        while !done.load(Ordering::SeqCst) {
+            unsafe {
+                libc::pthread_kill(
+                    std::os::unix::thread::JoinHandleExt::as_pthread_t(&handle),
+                    libc::SIGUSR1,
+                )
+            };
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        handle.join().unwrap()
+    }
+
+    #[test]
+    fn test_sleep_restart() {
+        let interval = TimeSpec::nanoseconds(20_000_000);
+        let start = get_time(ClockId::ClockMonotonic).unwrap();
+        interrupt(move || nanosleep_relative_restart(ClockId::ClockMonotonic, interval)).unwrap();
+        assert!(get_time(ClockId::ClockMonotonic).unwrap() - start >= interval);
+
+        let deadline = get_time(ClockId::ClockMonotonic).unwrap() + interval;
+        interrupt(move || nanosleep_absolute_restart(ClockId::ClockMonotonic, deadline)).unwrap();
+        assert!(get_time(ClockId::ClockMonotonic).unwrap() >= deadline);
+    }
+
+    #[test]
+    fn test_sleep_cancelable() {
+        let interval = TimeSpec::seconds(10);
+        let outcome = interrupt(move || {
+            nanosleep_relative_cancelable(ClockId::ClockMonotonic, interval, || true)
+        })
+        .unwrap();
+        let SleepOutcome::Cancelled(remaining) = outcome else {
+            panic!("sleep was not cancelled");
+        };
+        assert!(remaining > TimeSpec::zeroed() && remaining < interval);
+
+        let deadline = get_time(ClockId::ClockMonotonic).unwrap() + interval;
+        let outcome = interrupt(move || {
+            nanosleep_absolute_cancelable(ClockId::ClockMonotonic, deadline, || true)
+        })
+        .unwrap();
+        let SleepOutcome::Cancelled(remaining) = outcome else {
+            panic!("sleep was not cancelled");
+        };
+        assert!(remaining > TimeSpec::zeroed() && remaining < interval);
+
+        assert_eq!(
+            nanosleep_absolute_cancelable(ClockId::ClockMonotonic, TimeSpec::zeroed(), || true),
+            Ok(SleepOutcome::Completed)
+        );
+    }
 }
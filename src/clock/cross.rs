@@ -0,0 +1,153 @@
+use syscalls::Errno;
+
+use crate::{
+    clock::{get_time, ClockId},
+    TimeSpec,
+};
+
+/// Number of reading triples [cross_timestamp] takes.
+const DEFAULT_SAMPLES: usize = 16;
+
+/// Correlated readings of two clocks taken by [cross_timestamp].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrossTimestamp {
+    /// Reading of the first clock, at the midpoint of the sample window.
+    pub a: TimeSpec,
+    /// Reading of the second clock.
+    pub b: TimeSpec,
+    /// `b - a`. Adding it to a time of the first clock yields the
+    /// corresponding time of the second clock.
+    pub offset: TimeSpec,
+    /// Bound on the error of `offset`: half the time between the two
+    /// readings of the first clock.
+    pub uncertainty: TimeSpec,
+}
+
+/// Correlates the clocks `a` and `b` by reading `a`, `b`, `a` in a row and
+/// keeping the triple with the smallest window. The reading of `b` is
+/// assumed to have been taken at the midpoint of the two readings of `a`.
+///
+/// This is the user-space counterpart of `PTP_SYS_OFFSET_EXTENDED` for the
+/// kernel's own clocks.
+pub fn cross_timestamp(a: ClockId, b: ClockId) -> Result<CrossTimestamp, Errno> {
+    cross_timestamp_with_samples(a, b, DEFAULT_SAMPLES)
+}
+
+/// Like [cross_timestamp] but takes `samples` reading triples. Fails with
+/// `EINVAL` if `samples` is zero.
+pub fn cross_timestamp_with_samples(
+    a: ClockId,
+    b: ClockId,
+    samples: usize,
+) -> Result<CrossTimestamp, Errno> {
+    let mut best: Option<(TimeSpec, TimeSpec, TimeSpec)> = None;
+    for _ in 0..samples {
+        let before = get_time(a)?;
+        let reading = get_time(b)?;
+        let after = get_time(a)?;
+        let window = after - before;
+        if best.is_none_or(|(before, _, after)| window < after - before) {
+            best = Some((before, reading, after));
+        }
+    }
+    let (before, reading, after) = best.ok_or(Errno::EINVAL)?;
+    let half = (after - before) / 2;
+    let midpoint = before + half;
+    Ok(CrossTimestamp {
+        a: midpoint,
+        b: reading,
+        offset: reading - midpoint,
+        uncertainty: half,
+    })
+}
+
+/// Translates times between the domains of two clocks.
+///
+/// The offset is measured once on creation and on every
+/// [ClockConverter::refresh]. Clocks that are slewed differently, like
+/// `ClockMonotonic` and `ClockMonotonicRaw`, drift apart, so the converter
+/// should be refreshed periodically.
+#[derive(Debug, Clone)]
+pub struct ClockConverter {
+    from: ClockId,
+    to: ClockId,
+    samples: usize,
+    timestamp: CrossTimestamp,
+}
+
+impl ClockConverter {
+    /// Creates a converter from clock `from` to clock `to`.
+    pub fn new(from: ClockId, to: ClockId) -> Result<Self, Errno> {
+        Self::with_samples(from, to, DEFAULT_SAMPLES)
+    }
+
+    /// Creates a converter that takes `samples` reading triples on every refresh.
+    pub fn with_samples(from: ClockId, to: ClockId, samples: usize) -> Result<Self, Errno> {
+        let timestamp = cross_timestamp_with_samples(from, to, samples)?;
+        Ok(Self {
+            from,
+            to,
+            samples,
+            timestamp,
+        })
+    }
+
+    /// Measures the offset between the clocks again.
+    pub fn refresh(&mut self) -> Result<(), Errno> {
+        self.timestamp = cross_timestamp_with_samples(self.from, self.to, self.samples)?;
+        Ok(())
+    }
+
+    /// Returns the [CrossTimestamp] of the last measurement.
+    pub fn timestamp(&self) -> CrossTimestamp {
+        self.timestamp
+    }
+
+    /// Converts the time `ts` of the `from` clock into the domain of the `to` clock.
+    pub fn convert(&self, ts: TimeSpec) -> TimeSpec {
+        ts + self.timestamp.offset
+    }
+
+    /// Converts the time `ts` of the `to` clock into the domain of the `from` clock.
+    pub fn convert_back(&self, ts: TimeSpec) -> TimeSpec {
+        ts - self.timestamp.offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_clock() {
+        let ts = cross_timestamp(ClockId::ClockMonotonic, ClockId::ClockMonotonic).unwrap();
+        assert!(ts.offset.as_nanoseconds().abs() <= ts.uncertainty.as_nanoseconds());
+        assert!(ts.uncertainty >= TimeSpec::zeroed());
+    }
+
+    #[test]
+    fn test_no_samples() {
+        assert_eq!(
+            cross_timestamp_with_samples(ClockId::ClockMonotonic, ClockId::ClockBoottime, 0),
+            Err(Errno::EINVAL)
+        );
+    }
+
+    #[test]
+    fn test_converter() {
+        let mut converter =
+            ClockConverter::new(ClockId::ClockMonotonic, ClockId::ClockBoottime).unwrap();
+        // Boottime only differs by the time spent in suspend.
+        assert!(converter.timestamp().offset >= -converter.timestamp().uncertainty);
+
+        converter.refresh().unwrap();
+        let monotonic = get_time(ClockId::ClockMonotonic).unwrap();
+        let boottime = converter.convert(monotonic);
+        assert_eq!(converter.convert_back(boottime), monotonic);
+
+        let realtime = ClockConverter::new(ClockId::ClockRealtime, ClockId::ClockTai).unwrap();
+        let offset = realtime.timestamp().offset;
+        let seconds = (offset.as_nanoseconds() as f64 / 1e9).round() as i64;
+        assert!((offset - TimeSpec::seconds(seconds)).as_nanoseconds().abs() < 1_000_000);
+    }
+}
@@ -0,0 +1,195 @@
+use syscalls::Errno;
+
+use crate::{
+    clock::{adjust_time, ClockId, ClockState, Timex, TimexMode},
+    lowlevel::clock::Timeval,
+    TimeSpec,
+};
+
+/// Largest offset accepted by [slew] (`MAXPHASE`).
+const MAX_SLEW: TimeSpec = TimeSpec::nanoseconds(500_000_000);
+/// Largest frequency offset of the system clock in ppm (`MAXFREQ`).
+const MAX_FREQUENCY_PPM: f64 = 500.0;
+/// Largest value accepted by [set_max_error] and [set_est_error] (`NTP_PHASE_LIMIT`).
+const MAX_ERROR: TimeSpec = TimeSpec::seconds(16);
+/// Largest TAI offset accepted by the kernel (`MAX_TAI_OFFSET`).
+const MAX_TAI_OFFSET: i32 = 100_000;
+/// `USER_HZ` as used by the kernel to validate `ADJ_TICK`.
+const USER_HZ: i64 = 100;
+/// `freq` is in ppm with a 16-bit fractional part.
+const FREQUENCY_SCALE: f64 = 65536.0;
+
+fn apply(
+    clockid: ClockId,
+    modes: TimexMode,
+    f: impl FnOnce(&mut Timex),
+) -> Result<ClockState, Errno> {
+    let mut timex = Timex {
+        modes,
+        ..Default::default()
+    };
+    f(&mut timex);
+    adjust_time(clockid, &mut timex)
+}
+
+/// Gradually corrects the clock [ClockId] by `offset`.
+///
+/// For the system clock, the offset is handed to the kernel PLL, which only
+/// acts on it while `STA_PLL` is set. For PTP hardware clocks, the offset is
+/// corrected by the device's phase adjustment. The `offset` must lie within
+/// ±0.5 s, larger corrections need [step].
+pub fn slew(clockid: ClockId, offset: TimeSpec) -> Result<ClockState, Errno> {
+    if offset > MAX_SLEW || offset < -MAX_SLEW {
+        return Err(Errno::EINVAL);
+    }
+    apply(
+        clockid,
+        TimexMode::ADJ_OFFSET | TimexMode::ADJ_NANO,
+        |timex| {
+            timex.offset = offset.as_nanoseconds();
+        },
+    )
+}
+
+/// Steps the clock [ClockId] by `offset`. Negative offsets set the clock back.
+pub fn step(clockid: ClockId, offset: TimeSpec) -> Result<ClockState, Errno> {
+    apply(
+        clockid,
+        TimexMode::ADJ_SETOFFSET | TimexMode::ADJ_NANO,
+        |timex| {
+            timex.time = setoffset_time(offset);
+        },
+    )
+}
+
+/// `ADJ_SETOFFSET` requires a normalized `time` with `tv_usec` in
+/// [0, 999'999'999]; negative offsets are expressed through `tv_sec`.
+fn setoffset_time(offset: TimeSpec) -> Timeval {
+    let nanoseconds = offset.as_nanoseconds();
+    Timeval {
+        tv_sec: nanoseconds.div_euclid(1_000_000_000),
+        tv_usec: nanoseconds.rem_euclid(1_000_000_000),
+    }
+}
+
+/// Sets the frequency offset of the clock [ClockId] in parts per million.
+/// The system clock accepts values within ±500 ppm; dynamic clocks are
+/// limited by their hardware and report `ERANGE` if exceeded.
+pub fn set_frequency_ppm(clockid: ClockId, ppm: f64) -> Result<ClockState, Errno> {
+    let is_dynamic = matches!(clockid, ClockId::Dynamic(_));
+    if !ppm.is_finite() || (!is_dynamic && ppm.abs() > MAX_FREQUENCY_PPM) {
+        return Err(Errno::EINVAL);
+    }
+    apply(clockid, TimexMode::ADJ_FREQUENCY, |timex| {
+        timex.freq = (ppm * FREQUENCY_SCALE).round() as i64;
+    })
+}
+
+/// Returns the current frequency offset of the clock [ClockId] in parts per million.
+pub fn frequency_ppm(clockid: ClockId) -> Result<f64, Errno> {
+    let mut timex = Timex::default();
+    adjust_time(clockid, &mut timex)?;
+    Ok(timex.freq as f64 / FREQUENCY_SCALE)
+}
+
+fn error_microseconds(error: TimeSpec) -> Result<i64, Errno> {
+    if error < TimeSpec::zeroed() || error > MAX_ERROR {
+        return Err(Errno::EINVAL);
+    }
+    Ok(error.as_microseconds())
+}
+
+/// Sets the maximum error of the clock [ClockId]. The kernel keeps
+/// increasing it over time until it is set again. Must lie within [0, 16 s].
+pub fn set_max_error(clockid: ClockId, error: TimeSpec) -> Result<ClockState, Errno> {
+    let error = error_microseconds(error)?;
+    apply(clockid, TimexMode::ADJ_MAXERROR, |timex| {
+        timex.maxerror = error;
+    })
+}
+
+/// Sets the estimated error of the clock [ClockId]. Must lie within [0, 16 s].
+pub fn set_est_error(clockid: ClockId, error: TimeSpec) -> Result<ClockState, Errno> {
+    let error = error_microseconds(error)?;
+    apply(clockid, TimexMode::ADJ_ESTERROR, |timex| {
+        timex.esterror = error;
+    })
+}
+
+/// Sets the offset between TAI and UTC in seconds, which the kernel uses
+/// for [ClockId::ClockTai]. Must lie within [0, 100000].
+pub fn set_tai_offset(clockid: ClockId, seconds: i32) -> Result<ClockState, Errno> {
+    if !(0..=MAX_TAI_OFFSET).contains(&seconds) {
+        return Err(Errno::EINVAL);
+    }
+    apply(clockid, TimexMode::ADJ_TAI, |timex| {
+        timex.constant = i64::from(seconds);
+    })
+}
+
+/// Sets the number of microseconds that are added to the clock on every
+/// tick. Must lie within ±10 % of the nominal `1_000_000 / USER_HZ`.
+pub fn set_tick(clockid: ClockId, microseconds: i64) -> Result<ClockState, Errno> {
+    if !(900_000 / USER_HZ..=1_100_000 / USER_HZ).contains(&microseconds) {
+        return Err(Errno::EINVAL);
+    }
+    apply(clockid, TimexMode::ADJ_TICK, |timex| {
+        timex.tick = microseconds;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation() {
+        let clock = ClockId::ClockRealtime;
+        assert_eq!(slew(clock, TimeSpec::seconds(1)), Err(Errno::EINVAL));
+        assert_eq!(slew(clock, TimeSpec::seconds(-1)), Err(Errno::EINVAL));
+        assert_eq!(set_frequency_ppm(clock, 500.1), Err(Errno::EINVAL));
+        assert_eq!(set_frequency_ppm(clock, f64::NAN), Err(Errno::EINVAL));
+        assert_eq!(
+            set_max_error(clock, TimeSpec::nanoseconds(-1)),
+            Err(Errno::EINVAL)
+        );
+        assert_eq!(
+            set_est_error(clock, TimeSpec::seconds(17)),
+            Err(Errno::EINVAL)
+        );
+        assert_eq!(set_tai_offset(clock, -1), Err(Errno::EINVAL));
+        assert_eq!(set_tick(clock, 8_999), Err(Errno::EINVAL));
+        assert_eq!(set_tick(clock, 11_001), Err(Errno::EINVAL));
+    }
+
+    #[test]
+    fn test_setoffset_time() {
+        assert_eq!(
+            setoffset_time(TimeSpec::nanoseconds(1_500_000_000)),
+            Timeval {
+                tv_sec: 1,
+                tv_usec: 500_000_000
+            }
+        );
+        assert_eq!(
+            setoffset_time(TimeSpec::nanoseconds(-1_000)),
+            Timeval {
+                tv_sec: -1,
+                tv_usec: 999_999_000
+            }
+        );
+        assert_eq!(
+            setoffset_time(TimeSpec::seconds(-2)),
+            Timeval {
+                tv_sec: -2,
+                tv_usec: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_frequency_ppm() {
+        let ppm = frequency_ppm(ClockId::ClockRealtime).unwrap();
+        assert!(ppm.abs() <= MAX_FREQUENCY_PPM);
+    }
+}
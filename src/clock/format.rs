@@ -0,0 +1,217 @@
+use std::{fmt, str::FromStr};
+
+use syscalls::Errno;
+
+use crate::TimeSpec;
+
+const UNITS: [(&str, i128); 4] = [
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Formats the value with the largest unit that keeps the integer part
+/// non-zero and without trailing zeros, e.g. `1.5ms` or `2.000000123s`.
+impl fmt::Display for TimeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanoseconds = self.as_nanoseconds_i128();
+        if nanoseconds == 0 {
+            return f.write_str("0s");
+        }
+        let sign = if nanoseconds < 0 { "-" } else { "" };
+        let magnitude = nanoseconds.unsigned_abs();
+        let (unit, scale) = UNITS
+            .into_iter()
+            .find(|(_, scale)| magnitude >= *scale as u128)
+            .unwrap_or(("ns", 1));
+        let scale = scale as u128;
+        let (integer, fraction) = (magnitude / scale, magnitude % scale);
+        if fraction == 0 {
+            return write!(f, "{sign}{integer}{unit}");
+        }
+        let digits = scale.ilog10() as usize;
+        let fraction = format!("{fraction:0digits$}");
+        write!(
+            f,
+            "{sign}{integer}.{}{unit}",
+            fraction.trim_end_matches('0')
+        )
+    }
+}
+
+/// Parses durations such as `10ms`, `250us`, `1.5s` or `100ns`. `µs` is
+/// accepted for microseconds and a leading `-` for negative values. Digits
+/// beyond nanosecond precision are truncated.
+///
+/// Fails with `EINVAL` on malformed input and with `ERANGE` if the value
+/// does not fit into a [TimeSpec].
+impl FromStr for TimeSpec {
+    type Err = Errno;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or(Errno::EINVAL)?;
+        let (number, unit) = s.split_at(split);
+        let scale = match unit {
+            "s" => 1_000_000_000,
+            "ms" => 1_000_000,
+            "us" | "µs" => 1_000,
+            "ns" => 1,
+            _ => return Err(Errno::EINVAL),
+        };
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty()
+            || number.ends_with('.')
+            || !all_digits(integer)
+            || !all_digits(fraction)
+        {
+            return Err(Errno::EINVAL);
+        }
+
+        let mut nanoseconds = integer
+            .parse::<i128>()
+            .map_err(|_| Errno::ERANGE)?
+            .checked_mul(scale)
+            .ok_or(Errno::ERANGE)?;
+        let mut place = scale;
+        for digit in fraction.bytes() {
+            place /= 10;
+            if place == 0 {
+                break;
+            }
+            nanoseconds += i128::from(digit - b'0') * place;
+        }
+        if negative {
+            nanoseconds = -nanoseconds;
+        }
+        let nanoseconds = i64::try_from(nanoseconds).map_err(|_| Errno::ERANGE)?;
+        Ok(TimeSpec::nanoseconds(nanoseconds))
+    }
+}
+
+/// Formats a `ClockRealtime` or `ClockTai` reading as an RFC 3339 timestamp
+/// with nanosecond precision, e.g. `2016-12-31T23:59:59.500000000Z`.
+///
+/// The reading is rendered on its own timescale. To render a TAI reading
+/// as UTC, convert it with [crate::clock::LeapInfo::tai_to_realtime] first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rfc3339(pub TimeSpec);
+
+impl TimeSpec {
+    /// Returns a [Rfc3339] wrapper that formats the time since the epoch.
+    pub fn rfc3339(self) -> Rfc3339 {
+        Rfc3339(self)
+    }
+}
+
+impl fmt::Display for Rfc3339 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanoseconds = self.0.as_nanoseconds_i128();
+        let seconds = nanoseconds.div_euclid(1_000_000_000) as i64;
+        let subsec = nanoseconds.rem_euclid(1_000_000_000);
+        let (days, secs_of_day) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{subsec:09}Z",
+            secs_of_day / 3600,
+            secs_of_day % 3600 / 60,
+            secs_of_day % 60
+        )
+    }
+}
+
+/// Converts days since 1970-01-01 into a proleptic Gregorian date.
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(TimeSpec::zeroed().to_string(), "0s");
+        assert_eq!(TimeSpec::nanoseconds(100).to_string(), "100ns");
+        assert_eq!(TimeSpec::nanoseconds(1_500).to_string(), "1.5us");
+        assert_eq!(TimeSpec::nanoseconds(1_500_000).to_string(), "1.5ms");
+        assert_eq!(TimeSpec::nanoseconds(10_000_000).to_string(), "10ms");
+        assert_eq!(TimeSpec::seconds(3).to_string(), "3s");
+        assert_eq!(
+            TimeSpec::nanoseconds(2_000_000_123).to_string(),
+            "2.000000123s"
+        );
+        assert_eq!(TimeSpec::nanoseconds(-250_000).to_string(), "-250us");
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("10ms".parse(), Ok(TimeSpec::nanoseconds(10_000_000)));
+        assert_eq!("250us".parse(), Ok(TimeSpec::nanoseconds(250_000)));
+        assert_eq!("250µs".parse(), Ok(TimeSpec::nanoseconds(250_000)));
+        assert_eq!("1.5s".parse(), Ok(TimeSpec::nanoseconds(1_500_000_000)));
+        assert_eq!("100ns".parse(), Ok(TimeSpec::nanoseconds(100)));
+        assert_eq!(" -0.25ms ".parse(), Ok(TimeSpec::nanoseconds(-250_000)));
+        assert_eq!(
+            "1.0000000019s".parse(),
+            Ok(TimeSpec::nanoseconds(1_000_000_001))
+        );
+        assert_eq!("1.5ns".parse(), Ok(TimeSpec::nanoseconds(1)));
+
+        for invalid in [
+            "", "10", "ms", "1.s", ".5s", "1..5s", "10 ms", "10min", "1e3ns",
+        ] {
+            assert_eq!(invalid.parse::<TimeSpec>(), Err(Errno::EINVAL), "{invalid}");
+        }
+        assert_eq!("10000000000s".parse::<TimeSpec>(), Err(Errno::ERANGE));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for nanoseconds in [1, 999, 1_000, 1_234_567, 2_000_000_123, -42_000_000_000] {
+            let ts = TimeSpec::nanoseconds(nanoseconds);
+            assert_eq!(ts.to_string().parse(), Ok(ts));
+        }
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(
+            TimeSpec::zeroed().rfc3339().to_string(),
+            "1970-01-01T00:00:00.000000000Z"
+        );
+        assert_eq!(
+            (TimeSpec::seconds(1_483_228_799) + TimeSpec::nanoseconds(500_000_000))
+                .rfc3339()
+                .to_string(),
+            "2016-12-31T23:59:59.500000000Z"
+        );
+        assert_eq!(
+            TimeSpec::seconds(951_782_400).rfc3339().to_string(),
+            "2000-02-29T00:00:00.000000000Z"
+        );
+        assert_eq!(
+            TimeSpec::nanoseconds(-1).rfc3339().to_string(),
+            "1969-12-31T23:59:59.999999999Z"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use syscalls::Errno;
+
+use crate::{
+    clock::{adjust_time, get_time, ClockId, ClockState, StatusCodes, Timex, TimexMode},
+    TimeSpec,
+};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Direction of a leap second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leap {
+    /// 23:59:60 is inserted at the end of the UTC day.
+    Insert,
+    /// 23:59:59 is deleted at the end of the UTC day.
+    Delete,
+}
+
+/// A leap second announced to the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeapSecond {
+    /// Whether the second is inserted or deleted.
+    pub leap: Leap,
+    /// End of the UTC day at which the leap second takes effect, as a
+    /// `ClockRealtime` time (midnight of the following day).
+    pub at: TimeSpec,
+}
+
+/// Relation between `ClockRealtime` (UTC) and `ClockTai`.
+///
+/// [leap_info] takes a snapshot of the kernel state. The fields are public
+/// so that the conversions can also be applied with historical offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeapInfo {
+    /// TAI - UTC in seconds before `pending` takes effect.
+    pub tai_offset: i32,
+    /// Leap second scheduled for the end of the current UTC day.
+    pub pending: Option<LeapSecond>,
+    /// The [ClockState] reported by the kernel.
+    pub state: ClockState,
+}
+
+impl LeapInfo {
+    /// Converts a `ClockRealtime` time into a `ClockTai` time.
+    ///
+    /// During an inserted leap second the kernel repeats 23:59:59, so such
+    /// times are ambiguous and are mapped to their first occurrence.
+    pub fn realtime_to_tai(&self, ts: TimeSpec) -> TimeSpec {
+        let offset = TimeSpec::seconds(i64::from(self.tai_offset));
+        match self.pending {
+            Some(LeapSecond {
+                leap: Leap::Insert,
+                at,
+            }) if ts >= at => ts + offset + TimeSpec::seconds(1),
+            Some(LeapSecond {
+                leap: Leap::Delete,
+                at,
+            }) if ts >= at - TimeSpec::seconds(1) => ts + offset - TimeSpec::seconds(1),
+            _ => ts + offset,
+        }
+    }
+
+    /// Converts a `ClockTai` time into a `ClockRealtime` time.
+    ///
+    /// An inserted leap second (23:59:60) is rendered as a repeated
+    /// 23:59:59, like `ClockRealtime` does.
+    pub fn tai_to_realtime(&self, ts: TimeSpec) -> TimeSpec {
+        let offset = TimeSpec::seconds(i64::from(self.tai_offset));
+        match self.pending {
+            Some(LeapSecond {
+                leap: Leap::Insert,
+                at,
+            }) if ts >= at + offset => ts - offset - TimeSpec::seconds(1),
+            Some(LeapSecond {
+                leap: Leap::Delete,
+                at,
+            }) if ts >= at + offset - TimeSpec::seconds(1) => ts - offset + TimeSpec::seconds(1),
+            _ => ts - offset,
+        }
+    }
+}
+
+/// Returns the current TAI - UTC offset in seconds, as set by `ADJ_TAI`.
+pub fn tai_offset() -> Result<i32, Errno> {
+    let mut timex = Timex::default();
+    adjust_time(ClockId::ClockRealtime, &mut timex)?;
+    Ok(timex.tai)
+}
+
+/// Returns the TAI - UTC offset and any pending leap second of the kernel.
+pub fn leap_info() -> Result<LeapInfo, Errno> {
+    let mut timex = Timex::default();
+    let state = adjust_time(ClockId::ClockRealtime, &mut timex)?;
+    let leap = if timex.status.contains(StatusCodes::STA_INS) {
+        Some(Leap::Insert)
+    } else if timex.status.contains(StatusCodes::STA_DEL) {
+        Some(Leap::Delete)
+    } else {
+        None
+    };
+    // Once the leap second was applied, the kernel reports TIME_OOP or
+    // TIME_WAIT and `tai` already holds the new offset.
+    let pending = match (leap, state) {
+        (Some(leap), ClockState::Ok | ClockState::Ins | ClockState::Del) => {
+            let now = get_time(ClockId::ClockRealtime)?;
+            let day = now.tv_sec.div_euclid(SECONDS_PER_DAY);
+            Some(LeapSecond {
+                leap,
+                at: TimeSpec::seconds((day + 1) * SECONDS_PER_DAY),
+            })
+        }
+        _ => None,
+    };
+    Ok(LeapInfo {
+        tai_offset: timex.tai,
+        pending,
+        state,
+    })
+}
+
+/// Converts a `ClockRealtime` time into a `ClockTai` time using the current kernel state.
+pub fn realtime_to_tai(ts: TimeSpec) -> Result<TimeSpec, Errno> {
+    leap_info().map(|info| info.realtime_to_tai(ts))
+}
+
+/// Converts a `ClockTai` time into a `ClockRealtime` time using the current kernel state.
+pub fn tai_to_realtime(ts: TimeSpec) -> Result<TimeSpec, Errno> {
+    leap_info().map(|info| info.tai_to_realtime(ts))
+}
+
+fn set_leap_status(leap: Option<Leap>) -> Result<ClockState, Errno> {
+    let mut timex = Timex::default();
+    adjust_time(ClockId::ClockRealtime, &mut timex)?;
+    let mut status = timex
+        .status
+        .writable()
+        .difference(StatusCodes::STA_INS | StatusCodes::STA_DEL);
+    match leap {
+        Some(Leap::Insert) => status |= StatusCodes::STA_INS,
+        Some(Leap::Delete) => status |= StatusCodes::STA_DEL,
+        None => {}
+    }
+    timex.modes = TimexMode::ADJ_STATUS;
+    timex.status = status;
+    adjust_time(ClockId::ClockRealtime, &mut timex)
+}
+
+/// Schedules a leap second at the end of the current UTC day. The kernel
+/// applies it to `ClockRealtime` and increments or decrements the TAI offset.
+pub fn schedule_leap(leap: Leap) -> Result<ClockState, Errno> {
+    set_leap_status(Some(leap))
+}
+
+/// Cancels a leap second scheduled by [schedule_leap]. This also
+/// acknowledges a completed leap second, which moves the [ClockState] from
+/// `Wait` back to `Ok`.
+pub fn cancel_leap() -> Result<ClockState, Errno> {
+    set_leap_status(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIDNIGHT: TimeSpec = TimeSpec::seconds(1_483_228_800);
+
+    fn info(leap: Option<Leap>) -> LeapInfo {
+        LeapInfo {
+            tai_offset: 36,
+            pending: leap.map(|leap| LeapSecond { leap, at: MIDNIGHT }),
+            state: ClockState::Ok,
+        }
+    }
+
+    #[test]
+    fn test_no_leap() {
+        let info = info(None);
+        let ts = MIDNIGHT + TimeSpec::nanoseconds(1);
+        assert_eq!(info.realtime_to_tai(ts), ts + TimeSpec::seconds(36));
+        assert_eq!(info.tai_to_realtime(info.realtime_to_tai(ts)), ts);
+    }
+
+    #[test]
+    fn test_insert() {
+        let info = info(Some(Leap::Insert));
+        let before = MIDNIGHT - TimeSpec::nanoseconds(500_000_000);
+        let after = MIDNIGHT + TimeSpec::nanoseconds(500_000_000);
+        assert_eq!(info.realtime_to_tai(before), before + TimeSpec::seconds(36));
+        assert_eq!(info.realtime_to_tai(after), after + TimeSpec::seconds(37));
+        assert_eq!(info.tai_to_realtime(info.realtime_to_tai(before)), before);
+        assert_eq!(info.tai_to_realtime(info.realtime_to_tai(after)), after);
+
+        // 23:59:60.5 is rendered as a repeated 23:59:59.5
+        let leap = MIDNIGHT + TimeSpec::seconds(36) + TimeSpec::nanoseconds(500_000_000);
+        assert_eq!(info.tai_to_realtime(leap), before);
+    }
+
+    #[test]
+    fn test_delete() {
+        let info = info(Some(Leap::Delete));
+        let before = MIDNIGHT - TimeSpec::nanoseconds(1_500_000_000);
+        let after = MIDNIGHT + TimeSpec::nanoseconds(500_000_000);
+        assert_eq!(info.realtime_to_tai(before), before + TimeSpec::seconds(36));
+        assert_eq!(info.realtime_to_tai(after), after + TimeSpec::seconds(35));
+        assert_eq!(info.tai_to_realtime(info.realtime_to_tai(before)), before);
+        assert_eq!(info.tai_to_realtime(info.realtime_to_tai(after)), after);
+
+        // 23:59:58.5 is followed by 00:00:00.5 one second later.
+        assert_eq!(
+            info.realtime_to_tai(after) - info.realtime_to_tai(before),
+            TimeSpec::seconds(1)
+        );
+    }
+
+    #[test]
+    fn test_kernel_offset() {
+        let info = leap_info().unwrap();
+        assert_eq!(info.tai_offset, tai_offset().unwrap());
+        let realtime = get_time(ClockId::ClockRealtime).unwrap();
+        let tai = get_time(ClockId::ClockTai).unwrap();
+        let converted = realtime_to_tai(realtime).unwrap();
+        assert!((tai - converted).as_nanoseconds().abs() < 1_000_000_000);
+        if let Some(pending) = info.pending {
+            assert!(pending.at > realtime);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::ops::ControlFlow;
+
+use syscalls::Errno;
+
+use crate::{
+    clock::{get_time, nanosleep_absolute_restart, ClockId},
+    TimeSpec,
+};
+
+/// Decides how [Periodic] continues after one or more periods were missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CatchUp {
+    /// Drop the missed activations and continue with the next period
+    /// boundary that lies in the future. The schedule keeps its phase.
+    #[default]
+    Skip,
+    /// Deliver every missed activation. [Periodic::wait_next] returns
+    /// immediately until the schedule has caught up with the clock.
+    RunAll,
+    /// Restart the schedule one period after the late wakeup. The phase
+    /// of the schedule shifts by the lateness.
+    Resync,
+}
+
+/// A single activation of a [Periodic] task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    /// Number of the activation, starting at 0. Activations dropped by
+    /// [CatchUp::Skip] are counted as well.
+    pub index: u64,
+    /// The absolute time at which the activation was scheduled.
+    pub deadline: TimeSpec,
+    /// How late the wakeup was with respect to `deadline`.
+    pub lateness: TimeSpec,
+    /// Number of whole periods that elapsed after `deadline` before the
+    /// thread woke up. Zero if the activation was on time.
+    pub missed: u64,
+}
+
+/// A periodic activation schedule with absolute deadlines.
+///
+/// The deadlines are `phase + k * period` on the timeline of the clock, so
+/// tasks with the same period and phase wake up together, even across
+/// processes. Sleeping to absolute deadlines avoids the drift that
+/// accumulates with relative sleeps.
+///
+/// ```no_run
+/// use linux_rt::clock::{ClockId, Periodic};
+/// use linux_rt::TimeSpec;
+/// use std::ops::ControlFlow;
+///
+/// let mut task = Periodic::new(ClockId::ClockMonotonic, TimeSpec::nanoseconds(500_000)).unwrap();
+/// task.run(|tick| {
+///     if tick.missed > 0 {
+///         eprintln!("overrun at activation {}", tick.index);
+///     }
+///     ControlFlow::<()>::Continue(())
+/// })
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Periodic {
+    clockid: ClockId,
+    period: TimeSpec,
+    phase: TimeSpec,
+    catch_up: CatchUp,
+    next: Option<TimeSpec>,
+    index: u64,
+    missed: u64,
+    /// Activations below this index are accounted in `missed`. With
+    /// [CatchUp::RunAll] the catch-up ticks see the same missed deadlines
+    /// again.
+    counted: u64,
+}
+
+impl Periodic {
+    /// Creates a schedule with the given `period` on the clock [ClockId].
+    /// Fails with `EINVAL` if `period` is not positive.
+    pub fn new(clockid: ClockId, period: TimeSpec) -> Result<Self, Errno> {
+        if period <= TimeSpec::zeroed() {
+            return Err(Errno::EINVAL);
+        }
+        Ok(Self {
+            clockid,
+            period,
+            phase: TimeSpec::zeroed(),
+            catch_up: CatchUp::default(),
+            next: None,
+            index: 0,
+            missed: 0,
+            counted: 0,
+        })
+    }
+
+    /// Shifts the period boundaries by `phase` using the builder pattern.
+    pub fn with_phase(mut self, phase: TimeSpec) -> Self {
+        self.phase = phase;
+        self
+    }
+
+    /// Sets the [CatchUp] policy using the builder pattern.
+    pub fn with_catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Returns the [ClockId] the schedule runs on.
+    pub fn clock_id(&self) -> ClockId {
+        self.clockid
+    }
+
+    /// Returns the period of the schedule.
+    pub fn period(&self) -> TimeSpec {
+        self.period
+    }
+
+    /// Returns the next deadline, or `None` before the first [Periodic::wait_next].
+    pub fn next_deadline(&self) -> Option<TimeSpec> {
+        self.next
+    }
+
+    /// Returns the total number of missed periods so far.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    /// Sleeps until the next deadline and returns the resulting [Tick].
+    ///
+    /// The first call waits for the first period boundary after the current
+    /// time. Interruptions by signal handlers are resumed transparently.
+    pub fn wait_next(&mut self) -> Result<Tick, Errno> {
+        let deadline = match self.next {
+            Some(next) => next,
+            None => self.first_deadline()?,
+        };
+        nanosleep_absolute_restart(self.clockid, deadline)?;
+        let now = get_time(self.clockid)?;
+        let lateness = now - deadline;
+        let period_ns = self.period.as_nanoseconds_i128();
+        let missed = (lateness.as_nanoseconds_i128().max(0) / period_ns) as u64;
+
+        let tick = Tick {
+            index: self.index,
+            deadline,
+            lateness,
+            missed,
+        };
+        self.missed += match self.catch_up {
+            CatchUp::RunAll => {
+                let end = self.index + 1 + missed;
+                let counted = self.counted.max(self.index + 1);
+                self.counted = end.max(counted);
+                end.saturating_sub(counted)
+            }
+            CatchUp::Skip | CatchUp::Resync => missed,
+        };
+        self.next = Some(match self.catch_up {
+            CatchUp::Skip => {
+                self.index += 1 + missed;
+                TimeSpec::nanoseconds(
+                    (deadline.as_nanoseconds_i128() + period_ns * (1 + missed) as i128) as i64,
+                )
+            }
+            CatchUp::RunAll => {
+                self.index += 1;
+                deadline + self.period
+            }
+            CatchUp::Resync => {
+                self.index += 1;
+                if missed > 0 {
+                    now + self.period
+                } else {
+                    deadline + self.period
+                }
+            }
+        });
+        Ok(tick)
+    }
+
+    /// Calls `f` once per period until it returns [ControlFlow::Break].
+    pub fn run<B, F>(&mut self, mut f: F) -> Result<B, Errno>
+    where
+        F: FnMut(&Tick) -> ControlFlow<B>,
+    {
+        loop {
+            let tick = self.wait_next()?;
+            if let ControlFlow::Break(b) = f(&tick) {
+                return Ok(b);
+            }
+        }
+    }
+
+    fn first_deadline(&self) -> Result<TimeSpec, Errno> {
+        let now = get_time(self.clockid)?.as_nanoseconds_i128();
+        let period = self.period.as_nanoseconds_i128();
+        let phase = self.phase.as_nanoseconds_i128();
+        let k = (now - phase).div_euclid(period) + 1;
+        Ok(TimeSpec::nanoseconds((phase + k * period) as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::nanosleep_relative;
+
+    const PERIOD: TimeSpec = TimeSpec::nanoseconds(1_000_000);
+
+    #[test]
+    fn test_wait_next() {
+        let mut task = Periodic::new(ClockId::ClockMonotonic, PERIOD).unwrap();
+        let first = task.wait_next().unwrap();
+        assert_eq!(first.index, 0);
+        assert_eq!(first.deadline.as_nanoseconds() % PERIOD.as_nanoseconds(), 0);
+        assert!(first.lateness >= TimeSpec::zeroed());
+
+        let second = task.wait_next().unwrap();
+        assert_eq!(second.index, 1 + first.missed);
+        assert_eq!(
+            second.deadline,
+            first.deadline + PERIOD * (1 + first.missed as i32)
+        );
+    }
+
+    #[test]
+    fn test_phase() {
+        let phase = TimeSpec::nanoseconds(250_000);
+        let mut task = Periodic::new(ClockId::ClockMonotonic, PERIOD)
+            .unwrap()
+            .with_phase(phase);
+        let tick = task.wait_next().unwrap();
+        assert_eq!(
+            tick.deadline.as_nanoseconds() % PERIOD.as_nanoseconds(),
+            phase.as_nanoseconds()
+        );
+    }
+
+    #[test]
+    fn test_invalid_period() {
+        assert_eq!(
+            Periodic::new(ClockId::ClockMonotonic, TimeSpec::zeroed()).unwrap_err(),
+            Errno::EINVAL
+        );
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut task = Periodic::new(ClockId::ClockMonotonic, PERIOD).unwrap();
+        let first = task.wait_next().unwrap();
+        nanosleep_relative(ClockId::ClockMonotonic, PERIOD * 5).unwrap();
+        let late = task.wait_next().unwrap();
+        assert!(late.missed >= 3);
+        assert_eq!(
+            late.deadline,
+            first.deadline + PERIOD * (1 + first.missed as i32)
+        );
+        let next = task.next_deadline().unwrap();
+        assert_eq!(next, late.deadline + PERIOD * (1 + late.missed as i32));
+        assert_eq!(task.missed(), first.missed + late.missed);
+    }
+
+    #[test]
+    fn test_run_all() {
+        let mut task = Periodic::new(ClockId::ClockMonotonic, PERIOD)
+            .unwrap()
+            .with_catch_up(CatchUp::RunAll);
+        task.wait_next().unwrap();
+        nanosleep_relative(ClockId::ClockMonotonic, PERIOD * 5).unwrap();
+        let late = task.wait_next().unwrap();
+        assert!(late.missed >= 3);
+        let next = task.wait_next().unwrap();
+        assert_eq!(next.index, late.index + 1);
+        assert_eq!(next.deadline, late.deadline + PERIOD);
+    }
+
+    #[test]
+    fn test_run_all_missed() {
+        let period = PERIOD * 10;
+        let mut task = Periodic::new(ClockId::ClockMonotonic, period)
+            .unwrap()
+            .with_catch_up(CatchUp::RunAll);
+        let first = task.wait_next().unwrap();
+        nanosleep_relative(ClockId::ClockMonotonic, period * 5).unwrap();
+        let late = task.wait_next().unwrap();
+        assert!(late.missed >= 3);
+        // The catch-up ticks see the same missed deadlines again, plus new
+        // ones if the thread is delayed, which must be counted only once.
+        let mut missed = std::collections::HashSet::new();
+        let mut count = |tick: &Tick| missed.extend(tick.index + 1..=tick.index + tick.missed);
+        count(&first);
+        count(&late);
+        let mut tick = late;
+        while tick.missed > 0 {
+            let next = task.wait_next().unwrap();
+            assert!(next.missed <= tick.missed);
+            count(&next);
+            tick = next;
+        }
+        assert_eq!(task.missed(), missed.len() as u64);
+    }
+
+    #[test]
+    fn test_resync() {
+        let mut task = Periodic::new(ClockId::ClockMonotonic, PERIOD)
+            .unwrap()
+            .with_catch_up(CatchUp::Resync);
+        task.wait_next().unwrap();
+        nanosleep_relative(ClockId::ClockMonotonic, PERIOD * 5).unwrap();
+        let late = task.wait_next().unwrap();
+        assert!(late.missed >= 3);
+        let next = task.next_deadline().unwrap();
+        assert_eq!(next, late.deadline + late.lateness + PERIOD);
+    }
+
+    #[test]
+    fn test_run() {
+        let mut task = Periodic::new(ClockId::ClockMonotonic, PERIOD).unwrap();
+        let last = task
+            .run(|tick| {
+                if tick.index >= 3 {
+                    ControlFlow::Break(tick.index)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert!(last >= 3);
+    }
+}
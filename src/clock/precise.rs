@@ -0,0 +1,155 @@
+use syscalls::Errno;
+
+use crate::{
+    clock::{nanosleep_absolute_restart, ClockId},
+    TimeSpec,
+};
+
+/// Reads the clock with `clock_gettime` of the C library, which is served by
+/// the vDSO without a system call for most clocks.
+fn now(clockid: ClockId) -> Result<TimeSpec, Errno> {
+    let mut tp = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    Errno::result(unsafe { libc::clock_gettime(clockid.as_raw(), &mut tp) })?;
+    Ok(TimeSpec {
+        tv_sec: tp.tv_sec,
+        tv_nsec: tp.tv_nsec,
+    })
+}
+
+/// Sleeps with `clock_nanosleep` until `spin_margin` before `deadline` and
+/// then busy-waits on `clock_gettime` until the clock reaches `deadline`.
+///
+/// Returns how late the function returned with respect to `deadline`. This
+/// trades CPU time for wakeup precision beyond the timer slack and hrtimer
+/// latency, so it is meant for threads on isolated cores. `spin_margin`
+/// should cover the worst wakeup latency of the system.
+pub fn precise_sleep_until(
+    clockid: ClockId,
+    deadline: TimeSpec,
+    spin_margin: TimeSpec,
+) -> Result<TimeSpec, Errno> {
+    let wakeup = deadline - spin_margin.max(TimeSpec::zeroed());
+    if now(clockid)? < wakeup {
+        nanosleep_absolute_restart(clockid, wakeup)?;
+    }
+    spin_until(clockid, deadline)
+}
+
+fn spin_until(clockid: ClockId, deadline: TimeSpec) -> Result<TimeSpec, Errno> {
+    loop {
+        let time = now(clockid)?;
+        if time >= deadline {
+            return Ok(time - deadline);
+        }
+        std::hint::spin_loop();
+    }
+}
+
+/// Like [precise_sleep_until] but learns the spin margin from the observed
+/// wakeup latencies of `clock_nanosleep`.
+///
+/// The margin grows immediately to 125 % of a latency that exceeds it and
+/// decays slowly towards 125 % of smaller latencies, bounded by
+/// [PreciseSleeper::with_bounds].
+#[derive(Debug, Clone)]
+pub struct PreciseSleeper {
+    clockid: ClockId,
+    margin: TimeSpec,
+    min_margin: TimeSpec,
+    max_margin: TimeSpec,
+}
+
+impl PreciseSleeper {
+    /// Creates an adaptive sleeper on the clock [ClockId]. The margin starts
+    /// at 50 µs, the default timer slack, and is bounded to [1 µs, 1 ms].
+    pub fn new(clockid: ClockId) -> Self {
+        Self {
+            clockid,
+            margin: TimeSpec::nanoseconds(50_000),
+            min_margin: TimeSpec::nanoseconds(1_000),
+            max_margin: TimeSpec::nanoseconds(1_000_000),
+        }
+    }
+
+    /// Sets the bounds of the margin using the builder pattern.
+    pub fn with_bounds(mut self, min_margin: TimeSpec, max_margin: TimeSpec) -> Self {
+        self.min_margin = min_margin;
+        self.max_margin = max_margin.max(min_margin);
+        self.margin = self.margin.clamp(self.min_margin, self.max_margin);
+        self
+    }
+
+    /// Returns the current spin margin.
+    pub fn margin(&self) -> TimeSpec {
+        self.margin
+    }
+
+    /// Sleeps until `deadline` and returns how late it returned.
+    pub fn sleep_until(&mut self, deadline: TimeSpec) -> Result<TimeSpec, Errno> {
+        let wakeup = deadline - self.margin;
+        if now(self.clockid)? < wakeup {
+            nanosleep_absolute_restart(self.clockid, wakeup)?;
+            let latency = now(self.clockid)? - wakeup;
+            self.learn(latency);
+        }
+        spin_until(self.clockid, deadline)
+    }
+
+    fn learn(&mut self, latency: TimeSpec) {
+        let target = latency + latency / 4;
+        let margin = if target > self.margin {
+            target
+        } else {
+            self.margin - (self.margin - target) / 16
+        };
+        self.margin = margin.clamp(self.min_margin, self.max_margin);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::get_time;
+
+    #[test]
+    fn test_precise_sleep_until() {
+        let clock = ClockId::ClockMonotonic;
+        let deadline = get_time(clock).unwrap() + TimeSpec::nanoseconds(2_000_000);
+        let error = precise_sleep_until(clock, deadline, TimeSpec::nanoseconds(200_000)).unwrap();
+        assert!(error >= TimeSpec::zeroed());
+        assert!(get_time(clock).unwrap() >= deadline);
+
+        // A deadline in the past returns immediately.
+        let error = precise_sleep_until(clock, deadline, TimeSpec::zeroed()).unwrap();
+        assert!(error > TimeSpec::zeroed());
+    }
+
+    #[test]
+    fn test_learn() {
+        let mut sleeper = PreciseSleeper::new(ClockId::ClockMonotonic)
+            .with_bounds(TimeSpec::nanoseconds(1_000), TimeSpec::nanoseconds(100_000));
+        sleeper.learn(TimeSpec::nanoseconds(80_000));
+        assert_eq!(sleeper.margin(), TimeSpec::nanoseconds(100_000));
+        sleeper.learn(TimeSpec::nanoseconds(8_000));
+        assert_eq!(sleeper.margin(), TimeSpec::nanoseconds(94_375));
+        for _ in 0..1_000 {
+            sleeper.learn(TimeSpec::nanoseconds(0));
+        }
+        assert_eq!(sleeper.margin(), TimeSpec::nanoseconds(1_000));
+    }
+
+    #[test]
+    fn test_sleeper() {
+        let clock = ClockId::ClockMonotonic;
+        let mut sleeper = PreciseSleeper::new(clock);
+        let mut deadline = get_time(clock).unwrap();
+        for _ in 0..5 {
+            deadline = deadline + TimeSpec::nanoseconds(1_000_000);
+            let error = sleeper.sleep_until(deadline).unwrap();
+            assert!(error >= TimeSpec::zeroed());
+        }
+    }
+}
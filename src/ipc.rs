@@ -0,0 +1,174 @@
+use std::{
+    ffi::{c_int, CString},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+    ptr::NonNull,
+};
+
+use syscalls::Errno;
+
+use crate::{
+    lowlevel::{
+        memfd::{
+            fcntl, ftruncate, lseek, memfd_create, F_ADD_SEALS, F_DUPFD, F_GET_SEALS, F_SEAL_GROW,
+            F_SEAL_SEAL, F_SEAL_SHRINK, MFD_ALLOW_SEALING, MFD_CLOEXEC, SEEK_END,
+        },
+        mman::{munmap, MAP_SHARED},
+    },
+    mman::{map_locked, page_size},
+};
+
+mod ring;
+
+pub use ring::{Consumer, Producer, SpscRing};
+
+/// A shared memory region backed by a sealed `memfd`, which is populated
+/// and locked into RAM on every side that maps it. The mapping is removed
+/// and the file descriptor closed on drop.
+///
+/// The region is created with [SharedRegion::new] and handed to another
+/// process as a file descriptor, e.g. inherited by a child created with
+/// [SharedRegion::inheritable_fd] or sent over a Unix socket. The peer maps
+/// it with [SharedRegion::from_fd]. The size of the region is sealed with
+/// `F_SEAL_SHRINK` and `F_SEAL_GROW`, so no side can truncate it and crash
+/// the others with `SIGBUS`.
+#[derive(Debug)]
+pub struct SharedRegion {
+    fd: OwnedFd,
+    addr: NonNull<u8>,
+    len: usize,
+}
+
+unsafe impl Send for SharedRegion {}
+unsafe impl Sync for SharedRegion {}
+
+impl SharedRegion {
+    fn map(fd: OwnedFd, len: usize) -> Result<Self, Errno> {
+        let addr = map_locked(len, MAP_SHARED, fd.as_raw_fd())?;
+        Ok(Self { fd, addr, len })
+    }
+
+    /// Creates a zero-initialized region of at least `len` bytes, rounded up
+    /// to the page size. `name` is only used for debugging and shows up in
+    /// `/proc/<pid>/maps` as `/memfd:<name>`.
+    ///
+    /// Fails with `EINVAL` for a length of zero or a name containing a nul
+    /// byte, and with `EAGAIN` or `ENOMEM` if `RLIMIT_MEMLOCK` is exceeded.
+    pub fn new(name: &str, len: usize) -> Result<Self, Errno> {
+        if len == 0 {
+            return Err(Errno::EINVAL);
+        }
+        let len = len
+            .checked_next_multiple_of(page_size())
+            .ok_or(Errno::ENOMEM)?;
+        let name = CString::new(name).map_err(|_| Errno::EINVAL)?;
+        let fd = unsafe { memfd_create(name.as_ptr(), MFD_CLOEXEC | MFD_ALLOW_SEALING) }?;
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+        unsafe { ftruncate(fd.as_raw_fd(), len) }?;
+        let seals = F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_SEAL;
+        unsafe { fcntl(fd.as_raw_fd(), F_ADD_SEALS, seals) }?;
+        Self::map(fd, len)
+    }
+
+    /// Maps a region received from the creator. Fails with `EINVAL` if `fd`
+    /// is not sealed against shrinking and growing or empty.
+    pub fn from_fd(fd: OwnedFd) -> Result<Self, Errno> {
+        let seals = unsafe { fcntl(fd.as_raw_fd(), F_GET_SEALS, 0) }? as c_int;
+        let required = F_SEAL_SHRINK | F_SEAL_GROW;
+        if seals & required != required {
+            return Err(Errno::EINVAL);
+        }
+        let len = unsafe { lseek(fd.as_raw_fd(), 0, SEEK_END) }?;
+        if len == 0 {
+            return Err(Errno::EINVAL);
+        }
+        Self::map(fd, len)
+    }
+
+    /// Returns a duplicate of the file descriptor without the close-on-exec
+    /// flag, so that it is inherited by children spawned afterwards. The
+    /// child gets the number from [AsRawFd::as_raw_fd] on the returned
+    /// descriptor, e.g. as an argument, and should take ownership of it
+    /// with [OwnedFd::from_raw_fd] and [SharedRegion::from_fd].
+    pub fn inheritable_fd(&self) -> Result<OwnedFd, Errno> {
+        let fd = unsafe { fcntl(self.fd.as_raw_fd(), F_DUPFD, 0) }?;
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+    }
+
+    /// Returns the length of the region in bytes, a multiple of the page
+    /// size.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the region has a length of zero. This is never the
+    /// case: [SharedRegion::new] rounds the length up to whole pages, and
+    /// [SharedRegion::from_fd] refuses an empty file, whose size can no
+    /// longer change once sealed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a pointer to the page-aligned start of the region.
+    ///
+    /// The memory may be modified by other processes at any time, so it is
+    /// not exposed as a slice. Accesses shared with a peer have to be
+    /// synchronized, e.g. with atomics as in [SpscRing].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.addr.as_ptr()
+    }
+}
+
+impl AsFd for SharedRegion {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for SharedRegion {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl Drop for SharedRegion {
+    fn drop(&mut self) {
+        let _ = unsafe { munmap(self.addr.as_ptr().cast(), self.len) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mman::is_resident;
+
+    #[test]
+    fn test_shared_region() {
+        let region = SharedRegion::new("test", 1).unwrap();
+        assert_eq!(region.len(), page_size());
+        let seals = unsafe { fcntl(region.as_raw_fd(), F_GET_SEALS, 0) }.unwrap() as c_int;
+        assert_eq!(seals, F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_SEAL);
+        assert_eq!(
+            unsafe { ftruncate(region.as_raw_fd(), 0) },
+            Err(Errno::EPERM)
+        );
+
+        let peer = SharedRegion::from_fd(region.inheritable_fd().unwrap()).unwrap();
+        assert_eq!(peer.len(), region.len());
+        assert_ne!(peer.as_ptr(), region.as_ptr());
+        unsafe { region.as_ptr().write_volatile(42) };
+        assert_eq!(unsafe { peer.as_ptr().read_volatile() }, 42);
+        let pages = unsafe { std::slice::from_raw_parts(peer.as_ptr(), peer.len()) };
+        assert!(is_resident(pages).unwrap());
+
+        assert_eq!(SharedRegion::new("test", 0).unwrap_err(), Errno::EINVAL);
+        assert_eq!(SharedRegion::new("te\0st", 1).unwrap_err(), Errno::EINVAL);
+    }
+
+    #[test]
+    fn test_unsealed() {
+        let fd = unsafe { memfd_create(c"test".as_ptr(), MFD_CLOEXEC) }.unwrap();
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+        unsafe { ftruncate(fd.as_raw_fd(), page_size()) }.unwrap();
+        assert_eq!(SharedRegion::from_fd(fd).unwrap_err(), Errno::EINVAL);
+    }
+}
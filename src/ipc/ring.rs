@@ -0,0 +1,469 @@
+use std::{
+    marker::PhantomData,
+    ptr,
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use syscalls::Errno;
+
+use crate::{
+    ipc::SharedRegion,
+    lowlevel::{sched::pid_t, signal::kill},
+    mman::Plain,
+};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"lrt-spsc");
+
+/// Keeps the producer and consumer index on separate cache lines.
+#[repr(C, align(64))]
+struct CachePadded<T>(T);
+
+#[repr(C)]
+struct Header {
+    magic: u64,
+    capacity: u64,
+    slot_size: u64,
+    /// Process ID of the [Producer], or zero if the side is not claimed.
+    producer: AtomicU32,
+    /// Process ID of the [Consumer], or zero if the side is not claimed.
+    consumer: AtomicU32,
+    /// Index of the next slot to pop, written by the consumer.
+    head: CachePadded<AtomicUsize>,
+    /// Index of the next slot to push, written by the producer.
+    tail: CachePadded<AtomicUsize>,
+}
+
+const SLOTS_OFFSET: usize = size_of::<Header>();
+
+/// Returns `false` if the process `pid` does not exist anymore.
+fn is_alive(pid: u32) -> bool {
+    match pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => !matches!(unsafe { kill(pid, 0) }, Err(Errno::ESRCH)),
+        // Not a process ID, the header is corrupted.
+        _ => true,
+    }
+}
+
+/// Claims one side of the ring for the calling process and returns its
+/// process ID. A claim left by a terminated process is taken over.
+fn claim(owner: &AtomicU32) -> Result<u32, Errno> {
+    let pid = std::process::id();
+    let mut current = 0;
+    loop {
+        match owner.compare_exchange(current, pid, Ordering::Acquire, Ordering::Relaxed) {
+            Ok(_) => return Ok(pid),
+            Err(0) => current = 0,
+            Err(found) if found != pid && !is_alive(found) => current = found,
+            Err(_) => return Err(Errno::EBUSY),
+        }
+    }
+}
+
+/// Releases a claim made with [claim], unless it was taken over.
+fn release(owner: &AtomicU32, pid: u32) {
+    let _ = owner.compare_exchange(pid, 0, Ordering::Release, Ordering::Relaxed);
+}
+
+/// A bounded single-producer/single-consumer ring buffer of `T` in a
+/// [SharedRegion], for passing values between a real-time and a non-real-time
+/// thread or process.
+///
+/// [Producer::push] and [Consumer::pop] are wait-free: they never block,
+/// never allocate and never make a system call. As the region is locked,
+/// they do not take page faults either. The ring is handed to a peer with
+/// [SpscRing::region] and opened there with [SpscRing::from_region].
+///
+/// At most one [Producer] and one [Consumer] exist at a time across all
+/// processes sharing the ring. A claimed side is recorded with the process
+/// ID of its owner, so that a process restarted after a crash can claim it
+/// again once the crashed owner has been reaped. The peers therefore have
+/// to share a PID namespace.
+///
+/// ```no_run
+/// use linux_rt::ipc::SpscRing;
+///
+/// let ring = SpscRing::<f64>::new(1024).unwrap();
+/// let mut producer = ring.producer().unwrap();
+/// let mut consumer = ring.consumer().unwrap();
+/// producer.push(1.5).unwrap();
+/// assert_eq!(consumer.pop(), Some(1.5));
+/// ```
+#[derive(Debug)]
+pub struct SpscRing<T> {
+    region: Arc<SharedRegion>,
+    capacity: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Clone for SpscRing<T> {
+    fn clone(&self) -> Self {
+        Self {
+            region: self.region.clone(),
+            capacity: self.capacity,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Plain> SpscRing<T> {
+    fn region_len(capacity: usize) -> Result<usize, Errno> {
+        if align_of::<T>() > align_of::<Header>() {
+            return Err(Errno::EINVAL);
+        }
+        capacity
+            .checked_mul(size_of::<T>())
+            .and_then(|slots| slots.checked_add(SLOTS_OFFSET))
+            .ok_or(Errno::ENOMEM)
+    }
+
+    /// Creates an empty ring in a new [SharedRegion]. Fails with `EINVAL` if
+    /// `capacity` is not a power of two.
+    pub fn new(capacity: usize) -> Result<Self, Errno> {
+        if !capacity.is_power_of_two() {
+            return Err(Errno::EINVAL);
+        }
+        let region = SharedRegion::new("linux-rt-spsc", Self::region_len(capacity)?)?;
+        let header = Header {
+            magic: MAGIC,
+            capacity: capacity as u64,
+            slot_size: size_of::<T>() as u64,
+            producer: AtomicU32::new(0),
+            consumer: AtomicU32::new(0),
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+        };
+        unsafe { ptr::write(region.as_ptr().cast(), header) };
+        Ok(Self {
+            region: Arc::new(region),
+            capacity,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Opens a ring created by a peer with [SpscRing::new]. Fails with
+    /// `EINVAL` if the region does not contain a ring of `T`.
+    pub fn from_region(region: SharedRegion) -> Result<Self, Errno> {
+        let header = unsafe { &*region.as_ptr().cast::<Header>() };
+        let capacity = usize::try_from(header.capacity).map_err(|_| Errno::EINVAL)?;
+        if header.magic != MAGIC
+            || header.slot_size != size_of::<T>() as u64
+            || !capacity.is_power_of_two()
+            || Self::region_len(capacity)? > region.len()
+        {
+            return Err(Errno::EINVAL);
+        }
+        Ok(Self {
+            region: Arc::new(region),
+            capacity,
+            _marker: PhantomData,
+        })
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { &*self.region.as_ptr().cast() }
+    }
+
+    fn slot(&self, index: usize) -> *mut T {
+        // Masking keeps the slot in bounds even if a peer corrupts the
+        // indices.
+        let slots = unsafe { self.region.as_ptr().add(SLOTS_OFFSET) }.cast::<T>();
+        unsafe { slots.add(index & (self.capacity - 1)) }
+    }
+
+    /// Returns the region holding the ring, to be handed to a peer.
+    pub fn region(&self) -> &SharedRegion {
+        &self.region
+    }
+
+    /// Returns the maximum number of values in the ring.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of values in the ring. The value may be outdated
+    /// as soon as it is returned.
+    pub fn len(&self) -> usize {
+        let header = self.header();
+        let head = header.head.0.load(Ordering::Acquire);
+        let tail = header.tail.0.load(Ordering::Acquire);
+        tail.wrapping_sub(head).min(self.capacity)
+    }
+
+    /// Returns `true` if the ring is empty, see [SpscRing::len].
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Claims the producing side. Fails with `EBUSY` if a [Producer] exists
+    /// in this or another running process.
+    pub fn producer(&self) -> Result<Producer<T>, Errno> {
+        let header = self.header();
+        let pid = claim(&header.producer)?;
+        Ok(Producer {
+            pid,
+            tail: header.tail.0.load(Ordering::Relaxed),
+            head: header.head.0.load(Ordering::Acquire),
+            ring: self.clone(),
+        })
+    }
+
+    /// Claims the consuming side. Fails with `EBUSY` if a [Consumer] exists
+    /// in this or another running process.
+    pub fn consumer(&self) -> Result<Consumer<T>, Errno> {
+        let header = self.header();
+        let pid = claim(&header.consumer)?;
+        Ok(Consumer {
+            pid,
+            head: header.head.0.load(Ordering::Relaxed),
+            tail: header.tail.0.load(Ordering::Acquire),
+            ring: self.clone(),
+        })
+    }
+}
+
+/// The producing side of a [SpscRing]. Releases its claim on drop.
+#[derive(Debug)]
+pub struct Producer<T: Plain> {
+    ring: SpscRing<T>,
+    pid: u32,
+    tail: usize,
+    /// Last seen head, to avoid touching the consumer's cache line on
+    /// every push.
+    head: usize,
+}
+
+impl<T: Plain> Producer<T> {
+    /// Appends `value` to the ring, or returns it if the ring is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let capacity = self.ring.capacity;
+        if self.tail.wrapping_sub(self.head) >= capacity {
+            self.head = self.ring.header().head.0.load(Ordering::Acquire);
+            if self.tail.wrapping_sub(self.head) >= capacity {
+                return Err(value);
+            }
+        }
+        unsafe { ptr::write(self.ring.slot(self.tail), value) };
+        self.tail = self.tail.wrapping_add(1);
+        self.ring
+            .header()
+            .tail
+            .0
+            .store(self.tail, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns `true` if the next [Producer::push] would fail.
+    pub fn is_full(&self) -> bool {
+        let head = self.ring.header().head.0.load(Ordering::Acquire);
+        self.tail.wrapping_sub(head) >= self.ring.capacity
+    }
+
+    /// Returns the ring this producer belongs to.
+    pub fn ring(&self) -> &SpscRing<T> {
+        &self.ring
+    }
+}
+
+impl<T: Plain> Drop for Producer<T> {
+    fn drop(&mut self) {
+        release(&self.ring.header().producer, self.pid);
+    }
+}
+
+/// The consuming side of a [SpscRing]. Releases its claim on drop.
+#[derive(Debug)]
+pub struct Consumer<T: Plain> {
+    ring: SpscRing<T>,
+    pid: u32,
+    head: usize,
+    /// Last seen tail, to avoid touching the producer's cache line on
+    /// every pop.
+    tail: usize,
+}
+
+impl<T: Plain> Consumer<T> {
+    /// Removes the oldest value from the ring, or returns `None` if the ring
+    /// is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head == self.tail {
+            self.tail = self.ring.header().tail.0.load(Ordering::Acquire);
+            if self.head == self.tail {
+                return None;
+            }
+        }
+        let value = unsafe { ptr::read(self.ring.slot(self.head)) };
+        self.head = self.head.wrapping_add(1);
+        self.ring
+            .header()
+            .head
+            .0
+            .store(self.head, Ordering::Release);
+        Some(value)
+    }
+
+    /// Returns `true` if the next [Consumer::pop] would return `None`.
+    pub fn is_empty(&self) -> bool {
+        self.head == self.ring.header().tail.0.load(Ordering::Acquire)
+    }
+
+    /// Returns the ring this consumer belongs to.
+    pub fn ring(&self) -> &SpscRing<T> {
+        &self.ring
+    }
+}
+
+impl<T: Plain> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        release(&self.ring.header().consumer, self.pid);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let ring = SpscRing::<u32>::new(4).unwrap();
+        let mut producer = ring.producer().unwrap();
+        let mut consumer = ring.consumer().unwrap();
+        assert_eq!(ring.producer().unwrap_err(), Errno::EBUSY);
+        assert_eq!(ring.consumer().unwrap_err(), Errno::EBUSY);
+
+        assert_eq!(consumer.pop(), None);
+        for round in 0..10 {
+            for i in 0..4 {
+                producer.push(round * 4 + i).unwrap();
+            }
+            assert!(producer.is_full());
+            assert_eq!(producer.push(99), Err(99));
+            assert_eq!(ring.len(), 4);
+            for i in 0..4 {
+                assert_eq!(consumer.pop(), Some(round * 4 + i));
+            }
+            assert!(consumer.is_empty());
+        }
+
+        drop(producer);
+        ring.producer().unwrap();
+        assert_eq!(SpscRing::<u32>::new(3).unwrap_err(), Errno::EINVAL);
+    }
+
+    #[test]
+    fn test_from_region() {
+        let ring = SpscRing::<[f32; 2]>::new(8).unwrap();
+        let fd = ring.region().inheritable_fd().unwrap();
+        let peer = SpscRing::<[f32; 2]>::from_region(SharedRegion::from_fd(fd).unwrap()).unwrap();
+        assert_eq!(peer.capacity(), 8);
+        let mut producer = ring.producer().unwrap();
+        assert_eq!(peer.producer().unwrap_err(), Errno::EBUSY);
+        let mut consumer = peer.consumer().unwrap();
+        producer.push([1.0, 2.0]).unwrap();
+        assert_eq!(peer.len(), 1);
+        assert_eq!(consumer.pop(), Some([1.0, 2.0]));
+
+        let fd = ring.region().inheritable_fd().unwrap();
+        let wrong = SpscRing::<u32>::from_region(SharedRegion::from_fd(fd).unwrap());
+        assert_eq!(wrong.unwrap_err(), Errno::EINVAL);
+    }
+
+    #[test]
+    fn test_threads() {
+        const COUNT: u64 = 100_000;
+        let ring = SpscRing::<u64>::new(64).unwrap();
+        let mut producer = ring.producer().unwrap();
+        let mut consumer = ring.consumer().unwrap();
+        let thread = std::thread::spawn(move || {
+            for i in 0..COUNT {
+                while producer.push(i).is_err() {
+                    std::thread::yield_now();
+                }
+            }
+        });
+        for i in 0..COUNT {
+            loop {
+                if let Some(value) = consumer.pop() {
+                    assert_eq!(value, i);
+                    break;
+                }
+                std::thread::yield_now();
+            }
+        }
+        thread.join().unwrap();
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn test_fork() {
+        const COUNT: u64 = 10_000;
+        let ring = SpscRing::<u64>::new(16).unwrap();
+        match unsafe { nix::unistd::fork() }.unwrap() {
+            nix::unistd::ForkResult::Child => {
+                let code = match ring.consumer() {
+                    Ok(mut consumer) => {
+                        let mut expected = 0;
+                        while expected < COUNT {
+                            match consumer.pop() {
+                                Some(value) if value == expected => expected += 1,
+                                Some(_) => break,
+                                None => std::thread::yield_now(),
+                            }
+                        }
+                        if expected == COUNT {
+                            0
+                        } else {
+                            2
+                        }
+                    }
+                    Err(_) => 1,
+                };
+                unsafe { libc::_exit(code) };
+            }
+            nix::unistd::ForkResult::Parent { child } => {
+                let mut producer = ring.producer().unwrap();
+                for i in 0..COUNT {
+                    while producer.push(i).is_err() {
+                        std::thread::yield_now();
+                    }
+                }
+                let status = nix::sys::wait::waitpid(child, None).unwrap();
+                assert_eq!(status, nix::sys::wait::WaitStatus::Exited(child, 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_stale_claim() {
+        let ring = SpscRing::<u64>::new(16).unwrap();
+        match unsafe { nix::unistd::fork() }.unwrap() {
+            nix::unistd::ForkResult::Child => {
+                // Exit without releasing the claims, as on a crash.
+                let code = match (ring.producer(), ring.consumer()) {
+                    (Ok(producer), Ok(consumer)) => {
+                        std::mem::forget(producer);
+                        std::mem::forget(consumer);
+                        0
+                    }
+                    _ => 1,
+                };
+                unsafe { libc::_exit(code) };
+            }
+            nix::unistd::ForkResult::Parent { child } => {
+                let status = nix::sys::wait::waitpid(child, None).unwrap();
+                assert_eq!(status, nix::sys::wait::WaitStatus::Exited(child, 0));
+                assert_eq!(
+                    ring.header().producer.load(Ordering::Relaxed),
+                    child.as_raw() as u32
+                );
+                let producer = ring.producer().unwrap();
+                let _consumer = ring.consumer().unwrap();
+                assert_eq!(ring.producer().unwrap_err(), Errno::EBUSY);
+                drop(producer);
+                ring.producer().unwrap();
+            }
+        }
+    }
+}
@@ -1,10 +1,22 @@
 #[warn(missing_docs)]
 /// Time functions
 pub mod clock;
+/// Shared-memory inter-process communication
+pub mod ipc;
 mod lowlevel;
 /// Memory functions
 pub mod mman;
+/// Resource limits
+pub mod resource;
 /// Scheduling functions
 pub mod sched;
+#[cfg(feature = "serde")]
+mod serde_impls;
+/// Priority-inheritance synchronization primitives
+pub mod sync;
+/// Time namespaces
+pub mod timens;
+/// Timer file descriptors
+pub mod timerfd;
 pub use lowlevel::clock::TimeSpec;
 pub use lowlevel::sched::CpuSet;
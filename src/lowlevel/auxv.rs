@@ -0,0 +1,23 @@
+pub const AT_PAGESZ: usize = 6;
+
+/// Returns the value of the entry `key` in the auxiliary vector of the
+/// process. It neither allocates nor makes a system call, so it may be
+/// called from real-time threads.
+pub fn getauxval(key: usize) -> Option<usize> {
+    match unsafe { libc::getauxval(key as std::ffi::c_ulong) } {
+        0 => None,
+        value => Some(value as usize),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_getauxval() {
+        let page_size = getauxval(AT_PAGESZ).unwrap();
+        assert!(page_size.is_power_of_two());
+        assert_eq!(getauxval(usize::MAX), None);
+    }
+}
@@ -0,0 +1,61 @@
+use syscalls::{syscall, Errno, Sysno};
+
+pub const LINUX_CAPABILITY_VERSION_3: u32 = 0x20080522;
+
+pub const CAP_IPC_LOCK: u32 = 14;
+pub const CAP_SYS_NICE: u32 = 23;
+pub const CAP_WAKE_ALARM: u32 = 35;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CapUserHeader {
+    pub version: u32,
+    pub pid: std::ffi::c_int,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CapUserData {
+    pub effective: u32,
+    pub permitted: u32,
+    pub inheritable: u32,
+}
+
+/// Returns the capabilities of the thread `hdrp.pid`. With
+/// `_LINUX_CAPABILITY_VERSION_3`, `datap` must point to two [CapUserData].
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn capget(hdrp: *mut CapUserHeader, datap: *mut CapUserData) -> Result<usize, Errno> {
+    syscall!(Sysno::capget, hdrp, datap)
+}
+
+/// Checks whether the calling thread has the capability `cap` in its effective set.
+pub fn has_effective(cap: u32) -> Result<bool, Errno> {
+    let mut header = CapUserHeader {
+        version: LINUX_CAPABILITY_VERSION_3,
+        pid: 0,
+    };
+    let mut data = [CapUserData::default(); 2];
+    unsafe { capget(&mut header, data.as_mut_ptr()) }?;
+    let word = data.get(cap as usize / 32).ok_or(Errno::EINVAL)?;
+    Ok(word.effective & (1 << (cap % 32)) != 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_capget() {
+        // Capabilities belong to threads, like the result of capget.
+        let status = std::fs::read_to_string("/proc/thread-self/status").unwrap();
+        let cap_eff = status
+            .lines()
+            .find_map(|line| line.strip_prefix("CapEff:"))
+            .and_then(|bits| u64::from_str_radix(bits.trim(), 16).ok())
+            .unwrap();
+        for cap in [CAP_IPC_LOCK, CAP_SYS_NICE, CAP_WAKE_ALARM] {
+            assert_eq!(has_effective(cap), Ok(cap_eff & (1 << cap) != 0));
+        }
+        assert_eq!(has_effective(64), Err(Errno::EINVAL));
+    }
+}
@@ -26,9 +26,25 @@ pub const CLOCK_TAI: clockid_t = 11;
 
 pub const TIMER_ABSTIME: c_int = 0x01;
 
+pub const CLOCKFD: clockid_t = 3;
+pub const CLOCKFD_MASK: clockid_t = 0x07;
+
+/// Converts the file descriptor of a dynamic clock device into a `clockid_t`.
+pub const fn fd_to_clockid(fd: c_int) -> clockid_t {
+    ((!fd) << 3) | CLOCKFD
+}
+
+pub const TIME_OK: c_int = 0;
+pub const TIME_INS: c_int = 1;
+pub const TIME_DEL: c_int = 2;
+pub const TIME_OOP: c_int = 3;
+pub const TIME_WAIT: c_int = 4;
+pub const TIME_ERROR: c_int = 5;
+
 /// Time in seconds and microseconds.
 #[repr(C)]
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Timeval {
     /// Seconds
     pub tv_sec: std::ffi::c_long,
@@ -263,13 +279,13 @@ pub struct TimexRaw {
 impl TimexRaw {
     pub(crate) fn from_timex(timex: &Timex) -> Self {
         Self {
-            modes: timex.modes.as_raw(),
+            modes: timex.modes.bits(),
             _pad: 0,
             offset: timex.offset,
             freq: timex.freq,
             maxerror: timex.maxerror,
             esterror: timex.esterror,
-            status: timex.status.as_raw(),
+            status: timex.status.bits(),
             _pad2: 0,
             constant: timex.constant,
             precision: timex.precision,
@@ -291,12 +307,12 @@ impl TimexRaw {
     }
     pub(crate) fn into_timex(self) -> Timex {
         Timex {
-            modes: TimexMode::from_raw(self.modes),
+            modes: TimexMode::from_bits_retain(self.modes),
             offset: self.offset,
             freq: self.freq,
             maxerror: self.maxerror,
             esterror: self.esterror,
-            status: StatusCodes::from_raw(self.status),
+            status: StatusCodes::from_bits_retain(self.status),
             constant: self.constant,
             precision: self.precision,
             tolerance: self.tolerance,
@@ -0,0 +1,37 @@
+use std::ffi::c_int;
+
+use syscalls::{syscall, Errno, Sysno};
+
+use crate::lowlevel::clock::TimeSpec;
+
+pub const FUTEX_LOCK_PI: c_int = 6;
+pub const FUTEX_UNLOCK_PI: c_int = 7;
+pub const FUTEX_TRYLOCK_PI: c_int = 8;
+pub const FUTEX_WAIT_REQUEUE_PI: c_int = 11;
+pub const FUTEX_CMP_REQUEUE_PI: c_int = 12;
+pub const FUTEX_LOCK_PI2: c_int = 13;
+pub const FUTEX_PRIVATE_FLAG: c_int = 128;
+pub const FUTEX_CLOCK_REALTIME: c_int = 256;
+
+pub const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
+
+/// Performs the futex operation `op` on the futex word at `uaddr`.
+/// # Parameter
+///  * `timeout` nullable
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn futex(
+    uaddr: *const u32,
+    op: c_int,
+    val: u32,
+    timeout: *const TimeSpec,
+    uaddr2: *const u32,
+    val3: u32,
+) -> Result<usize, Errno> {
+    syscall!(Sysno::futex, uaddr, op, val, timeout, uaddr2, val3)
+}
+
+/// Returns the thread ID of the calling thread.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn gettid() -> Result<usize, Errno> {
+    syscall!(Sysno::gettid)
+}
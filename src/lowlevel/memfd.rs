@@ -0,0 +1,40 @@
+use std::ffi::{c_char, c_int, c_uint};
+
+use syscalls::{syscall, Errno, Sysno};
+
+pub const MFD_CLOEXEC: c_uint = 0x01;
+pub const MFD_ALLOW_SEALING: c_uint = 0x02;
+
+pub const F_DUPFD: c_int = 0;
+pub const F_ADD_SEALS: c_int = 1033;
+pub const F_GET_SEALS: c_int = 1034;
+
+pub const F_SEAL_SEAL: c_int = 0x01;
+pub const F_SEAL_SHRINK: c_int = 0x02;
+pub const F_SEAL_GROW: c_int = 0x04;
+
+pub const SEEK_END: c_int = 2;
+
+/// Creates an anonymous file and returns a file descriptor that refers to it.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn memfd_create(name: *const c_char, flags: c_uint) -> Result<usize, Errno> {
+    syscall!(Sysno::memfd_create, name, flags)
+}
+
+/// Performs the operation `cmd` with the integer argument `arg` on `fd`.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn fcntl(fd: c_int, cmd: c_int, arg: c_int) -> Result<usize, Errno> {
+    syscall!(Sysno::fcntl, fd, cmd, arg)
+}
+
+/// Truncates or extends the file referred to by `fd` to `length` bytes.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn ftruncate(fd: c_int, length: usize) -> Result<usize, Errno> {
+    syscall!(Sysno::ftruncate, fd, length)
+}
+
+/// Repositions the file offset of `fd` and returns the new offset.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn lseek(fd: c_int, offset: isize, whence: c_int) -> Result<usize, Errno> {
+    syscall!(Sysno::lseek, fd, offset, whence)
+}
@@ -34,6 +34,109 @@ pub mod flags {
     pub const MCL_ONFAULT: c_int = 0x8000;
 }
 
+pub const MLOCK_ONFAULT: c_int = 0x01;
+
+pub const MADV_WILLNEED: c_int = 3;
+pub const MADV_DONTFORK: c_int = 10;
+pub const MADV_DOFORK: c_int = 11;
+pub const MADV_HUGEPAGE: c_int = 14;
+pub const MADV_NOHUGEPAGE: c_int = 15;
+pub const MADV_DONTDUMP: c_int = 16;
+pub const MADV_DODUMP: c_int = 17;
+pub const MADV_POPULATE_READ: c_int = 22;
+pub const MADV_POPULATE_WRITE: c_int = 23;
+
+pub const PROT_READ: c_int = 0x1;
+pub const PROT_WRITE: c_int = 0x2;
+
+pub const MAP_SHARED: c_int = 0x01;
+pub const MAP_PRIVATE: c_int = 0x02;
+
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "arm",
+    target_arch = "x86",
+    target_arch = "s390x",
+    target_arch = "riscv64",
+    target_arch = "riscv32",
+    target_arch = "aarch64",
+))]
+pub mod map {
+    use std::ffi::c_int;
+
+    pub const MAP_ANONYMOUS: c_int = 0x20;
+    pub const MAP_LOCKED: c_int = 0x2000;
+    pub const MAP_POPULATE: c_int = 0x8000;
+    pub const MAP_HUGETLB: c_int = 0x40000;
+}
+
+#[cfg(any(target_arch = "powerpc", target_arch = "powerpc64"))]
+pub mod map {
+    use std::ffi::c_int;
+
+    pub const MAP_ANONYMOUS: c_int = 0x20;
+    pub const MAP_LOCKED: c_int = 0x80;
+    pub const MAP_POPULATE: c_int = 0x8000;
+    pub const MAP_HUGETLB: c_int = 0x40000;
+}
+
+#[cfg(any(target_arch = "sparc64", target_arch = "sparc"))]
+pub mod map {
+    use std::ffi::c_int;
+
+    pub const MAP_ANONYMOUS: c_int = 0x20;
+    pub const MAP_LOCKED: c_int = 0x100;
+    pub const MAP_POPULATE: c_int = 0x8000;
+    pub const MAP_HUGETLB: c_int = 0x40000;
+}
+
+#[cfg(any(target_arch = "mips", target_arch = "mips64"))]
+pub mod map {
+    use std::ffi::c_int;
+
+    pub const MAP_ANONYMOUS: c_int = 0x800;
+    pub const MAP_LOCKED: c_int = 0x8000;
+    pub const MAP_POPULATE: c_int = 0x10000;
+    pub const MAP_HUGETLB: c_int = 0x80000;
+}
+
+/// Creates a new mapping. Returns the address of the mapping.
+#[inline]
+#[cfg(target_pointer_width = "64")]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn mmap(
+    addr: *mut c_void,
+    len: usize,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    offset: i64,
+) -> Result<usize, Errno> {
+    syscall!(Sysno::mmap, addr, len, prot, flags, fd, offset)
+}
+
+/// Creates a new mapping. Returns the address of the mapping.
+/// `offset` must be a multiple of 4096.
+#[inline]
+#[cfg(target_pointer_width = "32")]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn mmap(
+    addr: *mut c_void,
+    len: usize,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    offset: i64,
+) -> Result<usize, Errno> {
+    syscall!(Sysno::mmap2, addr, len, prot, flags, fd, offset / 4096)
+}
+
+#[inline]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn munmap(addr: *mut c_void, len: usize) -> Result<usize, Errno> {
+    syscall!(Sysno::munmap, addr, len)
+}
+
 #[inline]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe fn mlock(addr: *const c_void, len: usize) -> Result<usize, Errno> {
@@ -52,6 +155,18 @@ pub unsafe fn munlock(addr: *const c_void, len: usize) -> Result<usize, Errno> {
     syscall!(Sysno::munlock, addr, len)
 }
 
+#[inline]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn mincore(addr: *mut c_void, len: usize, vec: *mut u8) -> Result<usize, Errno> {
+    syscall!(Sysno::mincore, addr, len, vec)
+}
+
+#[inline]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn madvise(addr: *mut c_void, len: usize, advice: c_int) -> Result<usize, Errno> {
+    syscall!(Sysno::madvise, addr, len, advice)
+}
+
 #[inline]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe fn mlockall(flags: c_int) -> Result<usize, Errno> {
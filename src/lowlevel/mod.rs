@@ -1,3 +1,18 @@
+pub mod auxv;
+pub mod capability;
 pub mod clock;
+pub mod futex;
+pub mod memfd;
 pub mod mman;
+pub mod prctl;
+pub mod resource;
 pub mod sched;
+pub mod signal;
+pub mod timens;
+pub mod timerfd;
+
+/// Converts the error of a file operation, e.g. on `/proc` or `/sys`, into
+/// the [Errno](syscalls::Errno) it carries, or `EIO` if it has none.
+pub(crate) fn io_errno(err: std::io::Error) -> syscalls::Errno {
+    syscalls::Errno::from_io_error(err).unwrap_or(syscalls::Errno::EIO)
+}
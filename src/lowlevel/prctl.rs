@@ -0,0 +1,21 @@
+use std::ffi::{c_int, c_ulong};
+
+use syscalls::{syscall, Errno, Sysno};
+
+pub const PR_SET_TIMERSLACK: c_int = 29;
+pub const PR_GET_TIMERSLACK: c_int = 30;
+pub const PR_SET_THP_DISABLE: c_int = 41;
+pub const PR_GET_THP_DISABLE: c_int = 42;
+
+/// Performs the operation `option` on the calling thread or process.
+/// Unused arguments must be zero.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn prctl(
+    option: c_int,
+    arg2: c_ulong,
+    arg3: c_ulong,
+    arg4: c_ulong,
+    arg5: c_ulong,
+) -> Result<usize, Errno> {
+    syscall!(Sysno::prctl, option, arg2, arg3, arg4, arg5)
+}
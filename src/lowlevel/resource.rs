@@ -0,0 +1,84 @@
+use std::ffi::{c_int, c_long};
+
+use syscalls::{syscall, Errno, Sysno};
+
+use crate::lowlevel::{clock::Timeval, sched::pid_t};
+
+pub const RLIMIT_STACK: u32 = 3;
+#[cfg(not(any(target_arch = "mips", target_arch = "mips64")))]
+pub const RLIMIT_MEMLOCK: u32 = 8;
+#[cfg(any(target_arch = "mips", target_arch = "mips64"))]
+pub const RLIMIT_MEMLOCK: u32 = 9;
+pub const RLIMIT_NICE: u32 = 13;
+pub const RLIMIT_RTPRIO: u32 = 14;
+pub const RLIMIT_RTTIME: u32 = 15;
+
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+pub const RUSAGE_SELF: c_int = 0;
+pub const RUSAGE_THREAD: c_int = 1;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rlimit64 {
+    /// Soft limit
+    pub rlim_cur: u64,
+    /// Hard limit (ceiling for rlim_cur)
+    pub rlim_max: u64,
+}
+
+/// Sets and/or gets the resource limit `resource` of the process `pid`.
+/// `new_limit` and `old_limit` may be null.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn prlimit64(
+    pid: pid_t,
+    resource: u32,
+    new_limit: *const Rlimit64,
+    old_limit: *mut Rlimit64,
+) -> Result<usize, Errno> {
+    syscall!(Sysno::prlimit64, pid, resource, new_limit, old_limit)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rusage {
+    /// User CPU time used
+    pub ru_utime: Timeval,
+    /// System CPU time used
+    pub ru_stime: Timeval,
+    /// Maximum resident set size
+    pub ru_maxrss: c_long,
+    /// Integral shared memory size
+    pub ru_ixrss: c_long,
+    /// Integral unshared data size
+    pub ru_idrss: c_long,
+    /// Integral unshared stack size
+    pub ru_isrss: c_long,
+    /// Page reclaims (soft page faults)
+    pub ru_minflt: c_long,
+    /// Page faults (hard page faults)
+    pub ru_majflt: c_long,
+    /// Swaps
+    pub ru_nswap: c_long,
+    /// Block input operations
+    pub ru_inblock: c_long,
+    /// Block output operations
+    pub ru_oublock: c_long,
+    /// IPC messages sent
+    pub ru_msgsnd: c_long,
+    /// IPC messages received
+    pub ru_msgrcv: c_long,
+    /// Signals received
+    pub ru_nsignals: c_long,
+    /// Voluntary context switches
+    pub ru_nvcsw: c_long,
+    /// Involuntary context switches
+    pub ru_nivcsw: c_long,
+}
+
+/// Returns resource usage measures for `who`, `RUSAGE_SELF` or
+/// `RUSAGE_THREAD`.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn getrusage(who: c_int, usage: *mut Rusage) -> Result<usize, Errno> {
+    syscall!(Sysno::getrusage, who, usage)
+}
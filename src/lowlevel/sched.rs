@@ -169,6 +169,68 @@ impl CpuSet {
     }
 }
 
+/// Formats the [CpuSet] as a cpulist string, e.g. `0-3,8,10-11`, as used
+/// by `/sys/devices/system/cpu/online` and `taskset -c`.
+impl std::fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut first = true;
+        let mut core = 0;
+        while core < Self::count() {
+            if !self.is_set(core) {
+                core += 1;
+                continue;
+            }
+            let start = core;
+            while core + 1 < Self::count() && self.is_set(core + 1) {
+                core += 1;
+            }
+            if !first {
+                f.write_str(",")?;
+            }
+            first = false;
+            if start == core {
+                write!(f, "{start}")?;
+            } else {
+                write!(f, "{start}-{core}")?;
+            }
+            core += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a cpulist string such as `0-3,8,10-11`. Fails with `EINVAL` on
+/// malformed input and for cores beyond [CpuSet::count].
+impl std::str::FromStr for CpuSet {
+    type Err = Errno;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cpuset = CpuSet::empty();
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(cpuset);
+        }
+        let parse = |core: &str| -> Result<usize, Errno> {
+            let core = core.trim().parse::<usize>().map_err(|_| Errno::EINVAL)?;
+            if core >= Self::count() {
+                return Err(Errno::EINVAL);
+            }
+            Ok(core)
+        };
+        for range in s.split(',') {
+            let (start, end) = match range.split_once('-') {
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                None => (parse(range)?, parse(range)?),
+            };
+            if start > end {
+                return Err(Errno::EINVAL);
+            }
+            (start..=end).for_each(|core| cpuset.set(core));
+        }
+        Ok(cpuset)
+    }
+}
+
 /// Sets the CPU affinity mask of the thread whose
 /// ID is pid to the value specified by mask.  If pid is zero, then
 /// the calling thread is used.  The argument cpusetsize is the length
@@ -344,6 +406,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_cpulist() {
+        let cpuset = CpuSet::from_slice([0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(cpuset.to_string(), "0-3,8,10-11");
+        assert_eq!("0-3,8,10-11".parse(), Ok(cpuset));
+        assert_eq!(" 5 ".parse(), Ok(CpuSet::empty().insert(5)));
+        assert_eq!("".parse(), Ok(CpuSet::empty()));
+        assert_eq!(CpuSet::empty().to_string(), "");
+        assert_eq!(
+            CpuSet::full().to_string(),
+            format!("0-{}", CpuSet::count() - 1)
+        );
+        for invalid in ["3-1", "a", "1,", "-1", "1-", "1024"] {
+            assert_eq!(invalid.parse::<CpuSet>(), Err(Errno::EINVAL), "{invalid}");
+        }
+    }
+
     #[test]
     fn test_affinity() {
         let mut cs_libc = unsafe { std::mem::zeroed() };
@@ -0,0 +1,12 @@
+use std::ffi::c_int;
+
+use syscalls::{syscall, Errno, Sysno};
+
+use crate::lowlevel::sched::pid_t;
+
+/// Sends the signal `sig` to the process `pid`. With a `sig` of zero, only
+/// checks whether the process exists and may be signaled.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn kill(pid: pid_t, sig: c_int) -> Result<usize, Errno> {
+    syscall!(Sysno::kill, pid, sig)
+}
@@ -0,0 +1,20 @@
+use std::ffi::c_int;
+
+use syscalls::{syscall, Errno, Sysno};
+
+pub const CLONE_NEWTIME: c_int = 0x80;
+
+/// Disassociates parts of the process execution context. With
+/// `CLONE_NEWTIME`, children of the calling process are placed in a new
+/// time namespace.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn unshare(flags: c_int) -> Result<usize, Errno> {
+    syscall!(Sysno::unshare, flags)
+}
+
+/// Reassociates the calling thread with the namespace referred to by `fd`.
+/// `nstype` restricts the namespace type, 0 allows any type.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn setns(fd: c_int, nstype: c_int) -> Result<usize, Errno> {
+    syscall!(Sysno::setns, fd, nstype)
+}
@@ -0,0 +1,105 @@
+use std::ffi::{c_int, c_void};
+
+use syscalls::{syscall, Errno, Sysno};
+
+use crate::lowlevel::clock::{clockid_t, TimeSpec};
+
+#[cfg(not(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "sparc",
+    target_arch = "sparc64"
+)))]
+pub mod flags {
+    use std::ffi::c_int;
+
+    pub const TFD_NONBLOCK: c_int = 0o4000;
+    pub const TFD_CLOEXEC: c_int = 0o2000000;
+}
+
+#[cfg(any(target_arch = "mips", target_arch = "mips64"))]
+pub mod flags {
+    use std::ffi::c_int;
+
+    pub const TFD_NONBLOCK: c_int = 0o200;
+    pub const TFD_CLOEXEC: c_int = 0o2000000;
+}
+
+#[cfg(any(target_arch = "sparc", target_arch = "sparc64"))]
+pub mod flags {
+    use std::ffi::c_int;
+
+    pub const TFD_NONBLOCK: c_int = 0x4000;
+    pub const TFD_CLOEXEC: c_int = 0x400000;
+}
+
+pub const TFD_TIMER_ABSTIME: c_int = 0x01;
+pub const TFD_TIMER_CANCEL_ON_SET: c_int = 0x02;
+
+/// Interval timer specification.
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ItimerSpec {
+    /// Interval for periodic timers. A zero interval makes the timer fire once.
+    pub it_interval: TimeSpec,
+    /// Initial expiration. A zero value disarms the timer.
+    pub it_value: TimeSpec,
+}
+
+/// Creates a new timer object and returns a file descriptor that refers to that timer.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn timerfd_create(clockid: clockid_t, flags: c_int) -> Result<usize, Errno> {
+    syscall!(Sysno::timerfd_create, clockid, flags)
+}
+
+/// Arms (starts) or disarms (stops) the timer referred to by the file descriptor `fd`.
+/// # Parameter
+///  * `old_value` nullable
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn timerfd_settime(
+    fd: c_int,
+    flags: c_int,
+    new_value: *const ItimerSpec,
+    old_value: *mut ItimerSpec,
+) -> Result<usize, Errno> {
+    syscall!(Sysno::timerfd_settime, fd, flags, new_value, old_value)
+}
+
+/// Returns the current setting of the timer referred to by the file descriptor `fd`.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn timerfd_gettime(fd: c_int, curr_value: *mut ItimerSpec) -> Result<usize, Errno> {
+    syscall!(Sysno::timerfd_gettime, fd, curr_value)
+}
+
+/// Reads up to `count` bytes from `fd` into `buf`.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn read(fd: c_int, buf: *mut c_void, count: usize) -> Result<usize, Errno> {
+    syscall!(Sysno::read, fd, buf, count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lowlevel::clock::CLOCK_MONOTONIC;
+
+    #[test]
+    fn test_timerfd() {
+        let fd = unsafe { timerfd_create(CLOCK_MONOTONIC, flags::TFD_CLOEXEC) }.unwrap() as c_int;
+        let new_value = ItimerSpec {
+            it_interval: TimeSpec::zeroed(),
+            it_value: TimeSpec::nanoseconds(1_000_000),
+        };
+        let ret = unsafe { timerfd_settime(fd, 0, &new_value, core::ptr::null_mut()) };
+        assert_eq!(ret, Ok(0));
+
+        let mut expirations = 0u64;
+        let ret = unsafe { read(fd, &raw mut expirations as *mut c_void, 8) };
+        assert_eq!(ret, Ok(8));
+        assert_eq!(expirations, 1);
+
+        let mut curr_value = ItimerSpec::default();
+        assert_eq!(unsafe { timerfd_gettime(fd, &mut curr_value) }, Ok(0));
+        assert_eq!(curr_value, ItimerSpec::default());
+        unsafe { libc::close(fd) };
+    }
+}
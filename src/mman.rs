@@ -1,13 +1,39 @@
-use std::ffi::c_void;
+use std::{ffi::c_void, sync::OnceLock};
 
 use bitflags::bitflags;
 use syscalls::Errno;
 
-use crate::lowlevel;
+use crate::lowlevel::{
+    self,
+    auxv::{getauxval, AT_PAGESZ},
+};
+
+mod advice;
+mod arena;
+mod buffer;
+mod detector;
+mod faults;
+mod lock;
+mod residency;
+mod stack;
+
+pub use advice::{
+    madvise, madvise_dont_fork, set_thp_disable, thp_disabled, Advice, ThpDefrag, ThpEnabled,
+    ThpSettings,
+};
+pub use arena::{ArenaAllocator, ArenaStats, Exhaustion};
+pub(crate) use buffer::map_locked;
+pub use buffer::{Plain, RtBuffer};
+pub use detector::{no_alloc_section, AllocDetector, AllocReport, NoAllocGuard, OnAlloc};
+pub use faults::{count_page_faults, FaultGuard, PageFaults};
+pub use lock::{lock_box, lock_slice, lock_slice_with, LockedBox, LockedRegion};
+pub use residency::{is_resident, mappings, mincore, unlocked_mappings, Mapping, MemoryStatus};
+pub use stack::prefault_stack;
 
 bitflags! {
     /// These flags control the scheduling behavior
     #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct MmanFlags: std::ffi::c_int {
         /// Lock all pages which are currently mapped into the address
         /// space of the process.
@@ -31,6 +57,24 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Flags of [mlock2]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Mlock2Flags: std::ffi::c_int {
+        /// Lock pages that are currently resident and mark the entire
+        /// range so that the remaining nonresident pages are locked when
+        /// they are populated by a page fault.
+        const MLOCK_ONFAULT = lowlevel::mman::MLOCK_ONFAULT;
+    }
+}
+
+/// Returns the size of a page in bytes.
+pub fn page_size() -> usize {
+    static PAGE_SIZE: OnceLock<usize> = OnceLock::new();
+    *PAGE_SIZE.get_or_init(|| getauxval(AT_PAGESZ).unwrap_or(4096))
+}
+
 /// locks pages in the address range starting at addr and
 /// continuing for size bytes.  All pages that contain a part of the
 /// specified address range are guaranteed to be resident in RAM when
@@ -45,10 +89,10 @@ pub unsafe fn mlock(addr: *const c_void, len: usize) -> Result<(), Errno> {
 /// and continuing for size bytes.  However, the state of the pages
 /// contained in that range after the call returns successfully will
 /// depend on the value in the flags argument.
-/// The flags argument can be either 0 or the following constant: MLOCK_ONFAULT
+/// The flags argument can be either empty or [Mlock2Flags::MLOCK_ONFAULT].
 /// # Safety
 /// See [here](https://man7.org/linux/man-pages/man2/munlock.2.html)
-pub unsafe fn mlock2(addr: *const c_void, len: usize, flags: MmanFlags) -> Result<(), Errno> {
+pub unsafe fn mlock2(addr: *const c_void, len: usize, flags: Mlock2Flags) -> Result<(), Errno> {
     lowlevel::mman::mlock2(addr, len, flags.bits()).map(|_| ())
 }
 /// unlocks pages in the address range starting at addr and
@@ -0,0 +1,286 @@
+use std::{ffi::c_void, fs};
+
+use syscalls::Errno;
+
+use crate::{
+    lowlevel::{
+        io_errno,
+        mman::{
+            madvise as raw_madvise, MADV_DODUMP, MADV_DOFORK, MADV_DONTDUMP, MADV_DONTFORK,
+            MADV_HUGEPAGE, MADV_NOHUGEPAGE, MADV_POPULATE_READ, MADV_POPULATE_WRITE, MADV_WILLNEED,
+        },
+        prctl::{prctl, PR_GET_THP_DISABLE, PR_SET_THP_DISABLE},
+    },
+    mman::{page_size, LockedRegion, RtBuffer},
+};
+
+/// Non-destructive advice for [madvise]. Advice that discards memory, such
+/// as `MADV_DONTNEED`, is not offered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advice {
+    /// Read the pages ahead, as they will be accessed soon.
+    WillNeed,
+    /// Allow transparent huge pages, e.g. with the THP mode `madvise`.
+    HugePage,
+    /// Never back the range with transparent huge pages, so that neither
+    /// page faults nor khugepaged compact memory for it.
+    NoHugePage,
+    /// Do not map the range into children after `fork`, e.g. to avoid
+    /// copy-on-write faults on locked memory in the parent. Only accepted
+    /// by [madvise_dont_fork].
+    DontFork,
+    /// Undo [Advice::DontFork].
+    DoFork,
+    /// Exclude the range from core dumps.
+    DontDump,
+    /// Undo [Advice::DontDump].
+    DoDump,
+    /// Fault in the pages readable, like a read access to every page, since
+    /// Linux 5.14.
+    PopulateRead,
+    /// Fault in the pages writable, like a write access to every page,
+    /// since Linux 5.14.
+    PopulateWrite,
+}
+
+impl Advice {
+    /// Converts an [Advice] into a raw `MADV_*` value.
+    pub fn as_raw(&self) -> std::ffi::c_int {
+        match self {
+            Advice::WillNeed => MADV_WILLNEED,
+            Advice::HugePage => MADV_HUGEPAGE,
+            Advice::NoHugePage => MADV_NOHUGEPAGE,
+            Advice::DontFork => MADV_DONTFORK,
+            Advice::DoFork => MADV_DOFORK,
+            Advice::DontDump => MADV_DONTDUMP,
+            Advice::DoDump => MADV_DODUMP,
+            Advice::PopulateRead => MADV_POPULATE_READ,
+            Advice::PopulateWrite => MADV_POPULATE_WRITE,
+        }
+    }
+}
+
+fn advise_range(addr: usize, len: usize, advice: Advice) -> Result<(), Errno> {
+    if len == 0 {
+        return Ok(());
+    }
+    let page_size = page_size();
+    let start = addr & !(page_size - 1);
+    let end = addr
+        .checked_add(len)
+        .and_then(|end| end.checked_next_multiple_of(page_size))
+        .ok_or(Errno::EINVAL)?;
+    unsafe { raw_madvise(start as *mut c_void, end - start, advice.as_raw()) }.and(Ok(()))
+}
+
+/// Gives `advice` about all pages containing a part of `slice`, including
+/// neighboring data on the first and last page.
+///
+/// Fails with `EINVAL` for [Advice::DontFork], which is only offered by
+/// [madvise_dont_fork] as it is not sound for arbitrary pages.
+pub fn madvise<T>(slice: &[T], advice: Advice) -> Result<(), Errno> {
+    if advice == Advice::DontFork {
+        return Err(Errno::EINVAL);
+    }
+    advise_range(slice.as_ptr() as usize, size_of_val(slice), advice)
+}
+
+/// Gives [Advice::DontFork] about all pages containing a part of `slice`,
+/// so that they are not mapped into children created with `fork`.
+///
+/// # Safety
+/// The advice applies to whole pages, including parts of neighboring data
+/// on the first and last page, e.g. other heap allocations and allocator
+/// metadata. A child created with `fork` must not access any data on these
+/// pages, as they are not mapped there.
+pub unsafe fn madvise_dont_fork<T>(slice: &[T]) -> Result<(), Errno> {
+    advise_range(
+        slice.as_ptr() as usize,
+        size_of_val(slice),
+        Advice::DontFork,
+    )
+}
+
+impl LockedRegion<'_> {
+    /// Gives `advice` about the locked pages, see [madvise].
+    pub fn advise(&self, advice: Advice) -> Result<(), Errno> {
+        if advice == Advice::DontFork {
+            return Err(Errno::EINVAL);
+        }
+        advise_range(self.addr() as usize, self.len(), advice)
+    }
+}
+
+impl RtBuffer {
+    /// Gives `advice` about the pages of the buffer, see [madvise].
+    pub fn advise(&self, advice: Advice) -> Result<(), Errno> {
+        madvise(self, advice)
+    }
+}
+
+/// Disables transparent huge pages for the calling process and its
+/// children created afterwards, or re-enables them.
+pub fn set_thp_disable(disable: bool) -> Result<(), Errno> {
+    unsafe { prctl(PR_SET_THP_DISABLE, disable.into(), 0, 0, 0) }.and(Ok(()))
+}
+
+/// Returns `true` if transparent huge pages are disabled for the calling
+/// process with [set_thp_disable].
+pub fn thp_disabled() -> Result<bool, Errno> {
+    unsafe { prctl(PR_GET_THP_DISABLE, 0, 0, 0, 0) }.map(|disabled| disabled != 0)
+}
+
+const THP_SYSFS: &str = "/sys/kernel/mm/transparent_hugepage";
+
+/// When transparent huge pages are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThpEnabled {
+    /// For all anonymous memory.
+    Always,
+    /// Only for ranges with [Advice::HugePage].
+    Madvise,
+    /// Never.
+    Never,
+}
+
+/// How the kernel compacts memory to allocate transparent huge pages on
+/// page faults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThpDefrag {
+    /// Stall on every fault until compaction succeeded.
+    Always,
+    /// Wake kswapd and kcompactd in the background.
+    Defer,
+    /// Stall for [Advice::HugePage] ranges, defer for the others.
+    DeferMadvise,
+    /// Stall for [Advice::HugePage] ranges only.
+    Madvise,
+    /// Never compact on faults.
+    Never,
+}
+
+/// The system-wide transparent huge page configuration from
+/// `/sys/kernel/mm/transparent_hugepage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThpSettings {
+    /// `enabled`
+    pub enabled: ThpEnabled,
+    /// `defrag`
+    pub defrag: ThpDefrag,
+    /// `khugepaged/defrag`, whether khugepaged collapses pages in the
+    /// background.
+    pub khugepaged_defrag: bool,
+}
+
+/// Returns the selected value of a sysfs choice such as
+/// `always [madvise] never`.
+fn selected(choice: &str) -> Option<&str> {
+    choice
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix('[')?.strip_suffix(']'))
+}
+
+fn read_sysfs(name: &str) -> Result<String, Errno> {
+    fs::read_to_string(format!("{THP_SYSFS}/{name}")).map_err(io_errno)
+}
+
+impl ThpSettings {
+    /// Reads the current settings. Fails with `ENOENT` if the kernel is
+    /// built without transparent huge pages.
+    pub fn read() -> Result<Self, Errno> {
+        Self::parse(
+            &read_sysfs("enabled")?,
+            &read_sysfs("defrag")?,
+            &read_sysfs("khugepaged/defrag")?,
+        )
+    }
+
+    fn parse(enabled: &str, defrag: &str, khugepaged_defrag: &str) -> Result<Self, Errno> {
+        let enabled = match selected(enabled) {
+            Some("always") => ThpEnabled::Always,
+            Some("madvise") => ThpEnabled::Madvise,
+            Some("never") => ThpEnabled::Never,
+            _ => return Err(Errno::EINVAL),
+        };
+        let defrag = match selected(defrag) {
+            Some("always") => ThpDefrag::Always,
+            Some("defer") => ThpDefrag::Defer,
+            Some("defer+madvise") => ThpDefrag::DeferMadvise,
+            Some("madvise") => ThpDefrag::Madvise,
+            Some("never") => ThpDefrag::Never,
+            _ => return Err(Errno::EINVAL),
+        };
+        let khugepaged_defrag = match khugepaged_defrag.trim() {
+            "0" => false,
+            "1" => true,
+            _ => return Err(Errno::EINVAL),
+        };
+        Ok(Self {
+            enabled,
+            defrag,
+            khugepaged_defrag,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mman::lock_slice;
+
+    #[test]
+    fn test_madvise() {
+        // Other tests fork concurrently, so the advice must not hit heap
+        // pages shared with their data.
+        let buffer = RtBuffer::new(4 * page_size()).unwrap();
+        madvise(&buffer, Advice::WillNeed).unwrap();
+        madvise(&buffer, Advice::NoHugePage).unwrap();
+        madvise(&buffer, Advice::DontDump).unwrap();
+        madvise(&buffer, Advice::DoDump).unwrap();
+        madvise::<u8>(&[], Advice::WillNeed).unwrap();
+        assert_eq!(madvise(&buffer, Advice::DontFork), Err(Errno::EINVAL));
+        unsafe { madvise_dont_fork(&buffer) }.unwrap();
+        buffer.advise(Advice::DoFork).unwrap();
+
+        let region = lock_slice(&buffer).unwrap();
+        assert_eq!(region.advise(Advice::DontFork), Err(Errno::EINVAL));
+        region.advise(Advice::DoFork).unwrap();
+        match region.advise(Advice::PopulateWrite) {
+            Ok(()) | Err(Errno::EINVAL) => {}
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    #[test]
+    fn test_thp_disable() {
+        let disabled = thp_disabled().unwrap();
+        set_thp_disable(true).unwrap();
+        assert!(thp_disabled().unwrap());
+        set_thp_disable(disabled).unwrap();
+        assert_eq!(thp_disabled(), Ok(disabled));
+    }
+
+    #[test]
+    fn test_thp_settings() {
+        assert_eq!(
+            ThpSettings::parse(
+                "always [madvise] never\n",
+                "always defer [defer+madvise] madvise never\n",
+                "1\n"
+            ),
+            Ok(ThpSettings {
+                enabled: ThpEnabled::Madvise,
+                defrag: ThpDefrag::DeferMadvise,
+                khugepaged_defrag: true,
+            })
+        );
+        assert_eq!(
+            ThpSettings::parse("always madvise never", "never", "0"),
+            Err(Errno::EINVAL)
+        );
+        match ThpSettings::read() {
+            Ok(_) | Err(Errno::ENOENT) => {}
+            Err(err) => panic!("{err}"),
+        }
+    }
+}
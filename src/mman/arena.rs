@@ -0,0 +1,352 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    ffi::c_void,
+    ptr,
+    sync::atomic::{AtomicI32, AtomicU8, AtomicUsize, Ordering},
+};
+
+use syscalls::{syscall, Errno, Sysno};
+
+use crate::{
+    lowlevel::mman::{
+        map::{MAP_ANONYMOUS, MAP_POPULATE},
+        mlock, mmap, munmap, MAP_PRIVATE, PROT_READ, PROT_WRITE,
+    },
+    sync::PiMutex,
+};
+
+/// Blocks are aligned to their size, up to this alignment.
+const MAX_ALIGN: usize = 4096;
+/// The smallest block holds the link of the free list.
+const MIN_BLOCK: usize = 16;
+
+const UNINIT: u8 = 0;
+const READY: u8 = 1;
+const FAILED: u8 = 2;
+
+/// What an [ArenaAllocator] does with an allocation it cannot serve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Exhaustion {
+    /// Print a message and abort the process.
+    #[default]
+    Abort,
+    /// Serve the allocation from the [System] allocator.
+    System,
+}
+
+/// Statistics of an [ArenaAllocator].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ArenaStats {
+    /// Size of the arena in bytes.
+    pub capacity: usize,
+    /// Bytes of the arena handed out at least once.
+    pub reserved: usize,
+    /// Bytes currently allocated, rounded up to block sizes.
+    pub used: usize,
+    /// Maximum of `used` so far.
+    pub high_water_mark: usize,
+    /// Allocations the arena could not serve.
+    pub failed_allocations: usize,
+}
+
+struct Heap {
+    offset: usize,
+    /// Heads of the free lists, one per power-of-two block size.
+    free: [usize; usize::BITS as usize],
+}
+
+/// A [GlobalAlloc] that serves allocations from an arena reserved with
+/// `mmap`, prefaulted and locked into RAM on first use. Freed memory is kept
+/// for reuse and never returned to the OS, so the heap of a real-time
+/// process does not take page faults once it has reached its steady state.
+///
+/// Allocations are rounded up to a power of two of at least 16 bytes and
+/// served from per-size free lists, falling back to the unused part of the
+/// arena. Allocations aligned to more than 4096 bytes or not fitting into the
+/// arena are handled according to [Exhaustion].
+///
+/// The heap is protected by a [PiMutex], so a real-time thread waiting for
+/// it boosts a preempted lower-priority holder instead of spinning.
+///
+/// ```no_run
+/// use linux_rt::mman::{ArenaAllocator, Exhaustion};
+///
+/// #[global_allocator]
+/// static ALLOCATOR: ArenaAllocator =
+///     ArenaAllocator::new(64 << 20).with_exhaustion(Exhaustion::System);
+///
+/// fn main() {
+///     ALLOCATOR.init().unwrap();
+///     // ...
+///     assert_eq!(ALLOCATOR.stats().failed_allocations, 0);
+/// }
+/// ```
+pub struct ArenaAllocator {
+    capacity: usize,
+    exhaustion: Exhaustion,
+    state: AtomicU8,
+    errno: AtomicI32,
+    /// Start of the arena, set once before `state` becomes `READY`.
+    base: AtomicUsize,
+    heap: PiMutex<Heap>,
+    used: AtomicUsize,
+    high_water_mark: AtomicUsize,
+    failed_allocations: AtomicUsize,
+}
+
+impl ArenaAllocator {
+    /// Creates an allocator with an arena of `capacity` bytes. The arena is
+    /// reserved by [ArenaAllocator::init] or on the first allocation.
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            exhaustion: Exhaustion::Abort,
+            state: AtomicU8::new(UNINIT),
+            errno: AtomicI32::new(0),
+            base: AtomicUsize::new(0),
+            heap: PiMutex::new(Heap {
+                offset: 0,
+                free: [0; usize::BITS as usize],
+            }),
+            used: AtomicUsize::new(0),
+            high_water_mark: AtomicUsize::new(0),
+            failed_allocations: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sets the [Exhaustion] policy using the builder pattern.
+    pub const fn with_exhaustion(mut self, exhaustion: Exhaustion) -> Self {
+        self.exhaustion = exhaustion;
+        self
+    }
+
+    /// Reserves, prefaults and locks the arena if not done yet. Fails if
+    /// the arena could not be set up, e.g. with `ENOMEM` or `EAGAIN` when
+    /// `RLIMIT_MEMLOCK` is too low. All allocations are then handled according
+    /// to [Exhaustion].
+    pub fn init(&self) -> Result<(), Errno> {
+        if self.state.load(Ordering::Acquire) == UNINIT {
+            // Concurrent callers block on the heap lock until the arena is
+            // set up.
+            let _heap = self.heap.lock()?;
+            if self.state.load(Ordering::Acquire) == UNINIT {
+                let state = match unsafe { self.reserve() } {
+                    Ok(()) => READY,
+                    Err(err) => {
+                        self.errno.store(err.into_raw(), Ordering::Relaxed);
+                        FAILED
+                    }
+                };
+                self.state.store(state, Ordering::Release);
+            }
+        }
+        match self.state.load(Ordering::Acquire) {
+            READY => Ok(()),
+            _ => Err(Errno::new(self.errno.load(Ordering::Relaxed))),
+        }
+    }
+
+    /// Returns the statistics of the arena.
+    pub fn stats(&self) -> ArenaStats {
+        let reserved = self.heap.lock().map_or(0, |heap| heap.offset);
+        ArenaStats {
+            capacity: self.capacity,
+            reserved,
+            used: self.used.load(Ordering::Relaxed),
+            high_water_mark: self.high_water_mark.load(Ordering::Relaxed),
+            failed_allocations: self.failed_allocations.load(Ordering::Relaxed),
+        }
+    }
+
+    /// # Safety
+    /// Must only be called once, with the heap locked.
+    unsafe fn reserve(&self) -> Result<(), Errno> {
+        let base = mmap(
+            ptr::null_mut(),
+            self.capacity,
+            PROT_READ | PROT_WRITE,
+            MAP_PRIVATE | MAP_ANONYMOUS | MAP_POPULATE,
+            -1,
+            0,
+        )?;
+        if let Err(err) = mlock(base as *const c_void, self.capacity) {
+            let _ = munmap(base as *mut c_void, self.capacity);
+            return Err(err);
+        }
+        self.base.store(base, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn contains(&self, ptr: *mut u8) -> bool {
+        if self.state.load(Ordering::Acquire) != READY {
+            return false;
+        }
+        let base = self.base.load(Ordering::Relaxed);
+        (base..base + self.capacity).contains(&(ptr as usize))
+    }
+
+    /// Returns the power-of-two block size class of `layout`.
+    fn class(layout: Layout) -> Option<usize> {
+        if layout.align() > MAX_ALIGN {
+            return None;
+        }
+        let size = layout.size().max(layout.align()).max(MIN_BLOCK);
+        Some(size.checked_next_power_of_two()?.trailing_zeros() as usize)
+    }
+
+    unsafe fn alloc_block(&self, class: usize) -> Option<*mut u8> {
+        let size = 1usize << class;
+        let base = self.base.load(Ordering::Relaxed);
+        let mut heap = self.heap.lock().ok()?;
+        let head = heap.free[class];
+        let block = if head != 0 {
+            heap.free[class] = *(head as *const usize);
+            head
+        } else {
+            let start = (base + heap.offset).next_multiple_of(size.min(MAX_ALIGN));
+            let end = start.checked_add(size)?;
+            if end > base + self.capacity {
+                return None;
+            }
+            heap.offset = end - base;
+            start
+        };
+        drop(heap);
+        let used = self.used.fetch_add(size, Ordering::Relaxed) + size;
+        self.high_water_mark.fetch_max(used, Ordering::Relaxed);
+        Some(block as *mut u8)
+    }
+
+    unsafe fn dealloc_block(&self, ptr: *mut u8, class: usize) {
+        // The lock only fails if the thread holds it already, which cannot
+        // happen as the heap does not allocate. The block leaks then.
+        let Ok(mut heap) = self.heap.lock() else {
+            return;
+        };
+        *(ptr as *mut usize) = heap.free[class];
+        heap.free[class] = ptr as usize;
+        drop(heap);
+        self.used.fetch_sub(1 << class, Ordering::Relaxed);
+    }
+
+    unsafe fn exhausted(&self, layout: Layout) -> *mut u8 {
+        self.failed_allocations.fetch_add(1, Ordering::Relaxed);
+        match self.exhaustion {
+            Exhaustion::System => System.alloc(layout),
+            Exhaustion::Abort => {
+                const MESSAGE: &[u8] = b"linux-rt: ArenaAllocator exhausted, aborting\n";
+                let _ = syscall!(Sysno::write, 2, MESSAGE.as_ptr(), MESSAGE.len());
+                std::process::abort()
+            }
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for ArenaAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if self.init().is_ok() {
+            if let Some(block) = Self::class(layout).and_then(|class| self.alloc_block(class)) {
+                return block;
+            }
+        }
+        self.exhausted(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        match Self::class(layout) {
+            Some(class) if self.contains(ptr) => self.dealloc_block(ptr, class),
+            _ => System.dealloc(ptr, layout),
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        if self.contains(ptr) && Self::class(layout) == Self::class(new_layout) {
+            return ptr;
+        }
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arena() {
+        let arena = ArenaAllocator::new(1 << 20).with_exhaustion(Exhaustion::System);
+        arena.init().unwrap();
+        let layout = Layout::from_size_align(100, 8).unwrap();
+        unsafe {
+            let a = arena.alloc(layout);
+            assert!(arena.contains(a));
+            assert_eq!(a as usize % 128, 0);
+            assert_eq!(arena.stats().used, 128);
+
+            // Growing within the block size keeps the block.
+            assert_eq!(arena.realloc(a, layout, 120), a);
+            let layout = Layout::from_size_align(120, 8).unwrap();
+            arena.dealloc(a, layout);
+            assert_eq!(arena.stats().used, 0);
+            // The freed block is reused.
+            assert_eq!(arena.alloc(layout), a);
+
+            let b = arena.realloc(a, layout, 4000);
+            assert_ne!(a, b);
+            assert_eq!(b as usize % 4096, 0);
+            let stats = arena.stats();
+            assert_eq!(stats.used, 4096);
+            assert_eq!(stats.high_water_mark, 4096 + 128);
+            arena.dealloc(b, Layout::from_size_align(4000, 8).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        let arena = std::sync::Arc::new(ArenaAllocator::new(1 << 20));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let arena = arena.clone();
+                std::thread::spawn(move || {
+                    let layout = Layout::from_size_align(64, 8).unwrap();
+                    for _ in 0..1000 {
+                        unsafe {
+                            let ptr = arena.alloc(layout);
+                            assert!(arena.contains(ptr));
+                            arena.dealloc(ptr, layout);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(arena.stats().used, 0);
+    }
+
+    #[test]
+    fn test_exhaustion() {
+        let arena = ArenaAllocator::new(1 << 16).with_exhaustion(Exhaustion::System);
+        let layout = Layout::from_size_align(1 << 17, 8).unwrap();
+        unsafe {
+            let ptr = arena.alloc(layout);
+            assert!(!ptr.is_null());
+            assert!(!arena.contains(ptr));
+            assert_eq!(arena.stats().failed_allocations, 1);
+            arena.dealloc(ptr, layout);
+
+            let layout = Layout::from_size_align(64, 8192).unwrap();
+            let ptr = arena.alloc(layout);
+            assert!(!arena.contains(ptr));
+            assert_eq!(arena.stats().failed_allocations, 2);
+            arena.dealloc(ptr, layout);
+        }
+        assert_eq!(arena.stats().used, 0);
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    ffi::{c_int, c_void},
+    fs,
+    ops::{Deref, DerefMut},
+    os::fd::RawFd,
+    ptr::{self, NonNull},
+    slice,
+};
+
+use syscalls::Errno;
+
+use crate::{
+    lowlevel::{
+        io_errno,
+        mman::{
+            map::{MAP_ANONYMOUS, MAP_HUGETLB, MAP_LOCKED, MAP_POPULATE},
+            mlock, mmap, munmap, MAP_PRIVATE, PROT_READ, PROT_WRITE,
+        },
+    },
+    mman::page_size,
+};
+
+/// Types that are valid for any bit pattern, including all zeros, and
+/// contain no pointers, so they can be viewed in raw memory.
+///
+/// # Safety
+/// Implementors must not have padding, invalid bit patterns or references.
+pub unsafe trait Plain: Copy + 'static {}
+
+macro_rules! impl_plain {
+    ($($t:ty),*) => {
+        $(unsafe impl Plain for $t {})*
+    };
+}
+
+impl_plain!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Plain, const N: usize> Plain for [T; N] {}
+
+/// Returns the default huge page size from `/proc/meminfo`. Fails with
+/// `ENOENT` if the kernel does not support huge pages.
+fn huge_page_size() -> Result<usize, Errno> {
+    let meminfo = fs::read_to_string("/proc/meminfo").map_err(io_errno)?;
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("Hugepagesize:"))
+        .and_then(|value| {
+            value
+                .trim()
+                .strip_suffix("kB")?
+                .trim()
+                .parse::<usize>()
+                .ok()
+        })
+        .map(|kb| kb * 1024)
+        .ok_or(Errno::ENOENT)
+}
+
+/// Maps `len` bytes of `fd` with `flags`, populated and locked into RAM.
+/// The mapping is removed again if it cannot be locked.
+pub(crate) fn map_locked(len: usize, flags: c_int, fd: RawFd) -> Result<NonNull<u8>, Errno> {
+    let flags = flags | MAP_POPULATE | MAP_LOCKED;
+    let addr = unsafe { mmap(ptr::null_mut(), len, PROT_READ | PROT_WRITE, flags, fd, 0) }?;
+    // MAP_LOCKED does not report a failure to populate, mlock does.
+    if let Err(err) = unsafe { mlock(addr as *const c_void, len) } {
+        let _ = unsafe { munmap(addr as *mut c_void, len) };
+        return Err(err);
+    }
+    NonNull::new(addr as *mut u8).ok_or(Errno::ENOMEM)
+}
+
+/// A zero-initialized anonymous mapping that is populated and locked into
+/// RAM from its creation on and unmapped on drop. It dereferences to `[u8]`.
+///
+/// Unlike memory locked after allocation, e.g. with [crate::mman::lock_slice],
+/// the buffer never takes a page fault.
+#[derive(Debug)]
+pub struct RtBuffer {
+    addr: NonNull<u8>,
+    len: usize,
+    mapped: usize,
+    huge: bool,
+}
+
+unsafe impl Send for RtBuffer {}
+unsafe impl Sync for RtBuffer {}
+
+impl RtBuffer {
+    fn map(len: usize, mapped: usize, flags: c_int) -> Result<Self, Errno> {
+        let addr = map_locked(mapped, MAP_PRIVATE | MAP_ANONYMOUS | flags, -1)?;
+        Ok(Self {
+            addr,
+            len,
+            mapped,
+            huge: flags & MAP_HUGETLB != 0,
+        })
+    }
+
+    /// Maps a buffer of `len` bytes backed by normal pages. Fails with
+    /// `EINVAL` for a length of zero and with `EAGAIN` or `ENOMEM` if
+    /// `RLIMIT_MEMLOCK` is exceeded.
+    pub fn new(len: usize) -> Result<Self, Errno> {
+        if len == 0 {
+            return Err(Errno::EINVAL);
+        }
+        let mapped = len
+            .checked_next_multiple_of(page_size())
+            .ok_or(Errno::ENOMEM)?;
+        Self::map(len, mapped, 0)
+    }
+
+    /// Maps a buffer of `len` bytes backed by huge pages of the default
+    /// size, which avoids TLB misses on large buffers. Falls back to normal
+    /// pages if the kernel does not support huge pages or the pool is
+    /// exhausted, see [RtBuffer::is_huge]. Other errors, e.g. `EAGAIN` if
+    /// `RLIMIT_MEMLOCK` is exceeded, are returned as with [RtBuffer::new].
+    pub fn with_huge_pages(len: usize) -> Result<Self, Errno> {
+        if len == 0 {
+            return Err(Errno::EINVAL);
+        }
+        let huge_page_size = match huge_page_size() {
+            Ok(size) => size,
+            Err(Errno::ENOENT) => return Self::new(len),
+            Err(err) => return Err(err),
+        };
+        let mapped = len
+            .checked_next_multiple_of(huge_page_size)
+            .ok_or(Errno::ENOMEM)?;
+        match Self::map(len, mapped, MAP_HUGETLB) {
+            // No huge pages are reserved, respectively too few are free.
+            Err(Errno::EINVAL | Errno::ENOMEM) => Self::new(len),
+            result => result,
+        }
+    }
+
+    /// Returns `true` if the buffer is backed by huge pages.
+    pub fn is_huge(&self) -> bool {
+        self.huge
+    }
+
+    /// Returns the length of the buffer in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the buffer has a length of zero, which is never the
+    /// case as empty buffers are rejected on creation.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the buffer as a slice of `T`. Trailing bytes that do not
+    /// fill a `T` are not part of the slice.
+    pub fn as_typed<T: Plain>(&self) -> &[T] {
+        // The mapping is page-aligned, which satisfies the alignment of any
+        // `Plain` type, and zero-initialized.
+        unsafe { slice::from_raw_parts(self.addr.as_ptr().cast(), self.typed_len::<T>()) }
+    }
+
+    /// Returns the buffer as a mutable slice of `T`, see [RtBuffer::as_typed].
+    pub fn as_typed_mut<T: Plain>(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.addr.as_ptr().cast(), self.typed_len::<T>()) }
+    }
+
+    fn typed_len<T>(&self) -> usize {
+        assert!(align_of::<T>() <= page_size(), "alignment exceeds a page");
+        self.len.checked_div(size_of::<T>()).unwrap_or(0)
+    }
+}
+
+impl Deref for RtBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_typed()
+    }
+}
+
+impl DerefMut for RtBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.as_typed_mut()
+    }
+}
+
+impl Drop for RtBuffer {
+    fn drop(&mut self) {
+        let _ = unsafe { munmap(self.addr.as_ptr().cast(), self.mapped) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mman::{count_page_faults, is_resident};
+
+    #[test]
+    fn test_rt_buffer() {
+        let mut buffer = RtBuffer::new(3 * page_size() + 1).unwrap();
+        assert_eq!(buffer.len(), 3 * page_size() + 1);
+        assert!(!buffer.is_huge());
+        assert!(is_resident(&buffer).unwrap());
+        assert!(buffer.iter().all(|&b| b == 0));
+
+        let ((), faults) = count_page_faults(|| buffer.fill(0xff)).unwrap();
+        assert_eq!(faults.total(), 0);
+
+        let samples = buffer.as_typed_mut::<f64>();
+        assert_eq!(samples.len(), (3 * page_size() + 1) / 8);
+        samples[1] = 1.5;
+        assert_eq!(buffer.as_typed::<[f64; 2]>()[0][1], 1.5);
+
+        assert_eq!(RtBuffer::new(0).unwrap_err(), Errno::EINVAL);
+    }
+
+    #[test]
+    fn test_huge_pages() {
+        let mut buffer = RtBuffer::with_huge_pages(1 << 20).unwrap();
+        assert_eq!(buffer.len(), 1 << 20);
+        buffer[(1 << 20) - 1] = 1;
+        assert!(is_resident(&buffer).unwrap());
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    backtrace::Backtrace,
+    cell::{Cell, RefCell},
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// What a [NoAllocGuard] does on an allocation inside its section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnAlloc {
+    /// Only count it.
+    #[default]
+    Count,
+    /// Count it and capture a backtrace.
+    Backtrace,
+    /// Count it and panic when the section is left. Allocators must not
+    /// unwind, so the panic cannot be raised at the allocation itself.
+    Panic,
+}
+
+#[derive(Clone, Copy)]
+enum Event {
+    Alloc,
+    Realloc,
+    Dealloc,
+}
+
+struct Section {
+    depth: Cell<usize>,
+    on_alloc: Cell<OnAlloc>,
+    /// Set while an event is recorded, so allocations of the recording
+    /// itself are not recorded.
+    busy: Cell<bool>,
+    allocations: Cell<usize>,
+    reallocations: Cell<usize>,
+    deallocations: Cell<usize>,
+}
+
+thread_local! {
+    static SECTION: Section = const {
+        Section {
+            depth: Cell::new(0),
+            on_alloc: Cell::new(OnAlloc::Count),
+            busy: Cell::new(false),
+            allocations: Cell::new(0),
+            reallocations: Cell::new(0),
+            deallocations: Cell::new(0),
+        }
+    };
+    static BACKTRACES: RefCell<Vec<Backtrace>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks the detector as installed. The flag is only stored once, so the
+/// cache line is not written on every allocation.
+#[inline]
+fn mark_installed() {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+}
+
+#[inline]
+fn check(event: Event) {
+    if SECTION.try_with(|s| s.depth.get()).unwrap_or(0) > 0 {
+        record(event);
+    }
+}
+
+#[cold]
+fn record(event: Event) {
+    let _ = SECTION.try_with(|s| {
+        if s.busy.replace(true) {
+            return;
+        }
+        let counter = match event {
+            Event::Alloc => &s.allocations,
+            Event::Realloc => &s.reallocations,
+            Event::Dealloc => &s.deallocations,
+        };
+        counter.set(counter.get() + 1);
+        if s.on_alloc.get() == OnAlloc::Backtrace {
+            let backtrace = Backtrace::force_capture();
+            let _ = BACKTRACES.try_with(|b| {
+                if let Ok(mut b) = b.try_borrow_mut() {
+                    b.push(backtrace);
+                }
+            });
+        }
+        s.busy.set(false);
+    });
+}
+
+/// A [GlobalAlloc] wrapper that records the allocations, reallocations and
+/// deallocations of a thread inside a [NoAllocGuard] or [no_alloc_section].
+///
+/// Outside of these sections it adds a thread-local load and a branch to
+/// every call of the wrapped allocator.
+///
+/// ```no_run
+/// use linux_rt::mman::{no_alloc_section, AllocDetector};
+/// use std::alloc::System;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: AllocDetector<System> = AllocDetector::new(System);
+///
+/// fn main() {
+///     let mut samples = Vec::with_capacity(16);
+///     let ((), report) = no_alloc_section(|| samples.push(1.0));
+///     assert!(report.is_clean());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct AllocDetector<A = System> {
+    inner: A,
+}
+
+impl<A> AllocDetector<A> {
+    /// Wraps the allocator `inner`.
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    /// Returns `true` if an [AllocDetector] serves the allocations of the
+    /// process, i.e. it is the global allocator.
+    pub fn is_installed() -> bool {
+        INSTALLED.load(Ordering::Relaxed)
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for AllocDetector<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        mark_installed();
+        check(Event::Alloc);
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        mark_installed();
+        check(Event::Alloc);
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        check(Event::Dealloc);
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        check(Event::Realloc);
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Heap operations of the current thread inside a section.
+#[derive(Debug, Default)]
+pub struct AllocReport {
+    /// Number of allocations.
+    pub allocations: usize,
+    /// Number of reallocations.
+    pub reallocations: usize,
+    /// Number of deallocations.
+    pub deallocations: usize,
+    /// Backtraces of all operations with [OnAlloc::Backtrace].
+    pub backtraces: Vec<Backtrace>,
+}
+
+impl AllocReport {
+    /// Returns `true` if the section did not touch the heap.
+    pub fn is_clean(&self) -> bool {
+        self.allocations == 0 && self.reallocations == 0 && self.deallocations == 0
+    }
+}
+
+/// Marks a section of the current thread that must not touch the heap,
+/// until the guard is dropped or [NoAllocGuard::finish]ed. Sections can be
+/// nested.
+///
+/// Requires [AllocDetector] to be the global allocator.
+#[derive(Debug)]
+pub struct NoAllocGuard {
+    on_alloc: OnAlloc,
+    previous: OnAlloc,
+    start: [usize; 3],
+    backtraces: usize,
+    active: bool,
+    _thread: PhantomData<*const ()>,
+}
+
+impl NoAllocGuard {
+    /// Enters a section.
+    ///
+    /// # Panics
+    /// If [AllocDetector] is not the global allocator.
+    pub fn new(on_alloc: OnAlloc) -> Self {
+        assert!(
+            AllocDetector::<System>::is_installed(),
+            "AllocDetector is not the global allocator"
+        );
+        let backtraces = BACKTRACES.with(|b| b.borrow().len());
+        SECTION.with(|s| {
+            s.depth.set(s.depth.get() + 1);
+            Self {
+                on_alloc,
+                previous: s.on_alloc.replace(on_alloc),
+                start: [
+                    s.allocations.get(),
+                    s.reallocations.get(),
+                    s.deallocations.get(),
+                ],
+                backtraces,
+                active: true,
+                _thread: PhantomData,
+            }
+        })
+    }
+
+    /// Returns the number of heap operations in the section so far.
+    pub fn count(&self) -> usize {
+        SECTION.with(|s| {
+            s.allocations.get() + s.reallocations.get() + s.deallocations.get()
+                - self.start.iter().sum::<usize>()
+        })
+    }
+
+    /// Leaves the section and returns its report.
+    ///
+    /// # Panics
+    /// With [OnAlloc::Panic], if the section touched the heap.
+    pub fn finish(mut self) -> AllocReport {
+        self.leave()
+    }
+
+    fn leave(&mut self) -> AllocReport {
+        self.active = false;
+        let (allocations, reallocations, deallocations) = SECTION.with(|s| {
+            s.depth.set(s.depth.get() - 1);
+            s.on_alloc.set(self.previous);
+            (
+                s.allocations.get() - self.start[0],
+                s.reallocations.get() - self.start[1],
+                s.deallocations.get() - self.start[2],
+            )
+        });
+        let backtraces = BACKTRACES.with(|b| {
+            let mut b = b.borrow_mut();
+            let start = self.backtraces.min(b.len());
+            b.split_off(start)
+        });
+        let report = AllocReport {
+            allocations,
+            reallocations,
+            deallocations,
+            backtraces,
+        };
+        if self.on_alloc == OnAlloc::Panic && !report.is_clean() && !std::thread::panicking() {
+            panic!(
+                "heap used in a no-alloc section: {} allocations, {} reallocations, {} deallocations",
+                report.allocations, report.reallocations, report.deallocations
+            );
+        }
+        report
+    }
+}
+
+impl Drop for NoAllocGuard {
+    fn drop(&mut self) {
+        if self.active {
+            self.leave();
+        }
+    }
+}
+
+/// Runs `f` in a section that must not touch the heap and returns its
+/// result with the report of the section. See [NoAllocGuard].
+pub fn no_alloc_section<R>(f: impl FnOnce() -> R) -> (R, AllocReport) {
+    let guard = NoAllocGuard::new(OnAlloc::Count);
+    let result = f();
+    (result, guard.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[global_allocator]
+    static ALLOCATOR: AllocDetector = AllocDetector::new(System);
+
+    #[test]
+    fn test_no_alloc_section() {
+        let mut v: Vec<u32> = Vec::with_capacity(4);
+        let ((), report) = no_alloc_section(|| v.extend([1, 2, 3]));
+        assert!(report.is_clean());
+
+        let (b, report) = no_alloc_section(|| {
+            v.extend([4, 5]);
+            Box::new(1)
+        });
+        assert_eq!(report.allocations, 1);
+        assert_eq!(report.reallocations, 1);
+        assert!(report.backtraces.is_empty());
+        drop(b);
+    }
+
+    #[test]
+    fn test_nested() {
+        let outer = NoAllocGuard::new(OnAlloc::Count);
+        let inner = NoAllocGuard::new(OnAlloc::Backtrace);
+        drop(vec![1u8; 8]);
+        assert_eq!(inner.count(), 2);
+        let report = inner.finish();
+        assert_eq!((report.allocations, report.deallocations), (1, 1));
+        assert_eq!(report.backtraces.len(), 2);
+        drop(report);
+        assert!(outer.count() >= 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "heap used in a no-alloc section")]
+    fn test_panic() {
+        let _guard = NoAllocGuard::new(OnAlloc::Panic);
+        drop(String::from("allocates"));
+    }
+
+    #[test]
+    #[should_panic(expected = "heap used in a no-alloc section")]
+    fn test_panic_finish() {
+        let guard = NoAllocGuard::new(OnAlloc::Panic);
+        drop(String::from("allocates"));
+        guard.finish();
+    }
+}
@@ -0,0 +1,166 @@
+use std::marker::PhantomData;
+
+use syscalls::Errno;
+
+use crate::lowlevel::resource::{getrusage, Rusage, RUSAGE_SELF, RUSAGE_THREAD};
+
+/// Page fault counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PageFaults {
+    /// Faults served without I/O, e.g. first touches of anonymous memory.
+    pub minor: u64,
+    /// Faults that required I/O, e.g. reading a page from disk or swap.
+    pub major: u64,
+}
+
+impl PageFaults {
+    fn get(who: std::ffi::c_int) -> Result<Self, Errno> {
+        let mut usage = Rusage::default();
+        unsafe { getrusage(who, &mut usage) }?;
+        Ok(Self {
+            minor: usage.ru_minflt as u64,
+            major: usage.ru_majflt as u64,
+        })
+    }
+
+    /// Returns the page faults of the calling thread so far.
+    pub fn thread() -> Result<Self, Errno> {
+        Self::get(RUSAGE_THREAD)
+    }
+
+    /// Returns the page faults of the calling process so far.
+    pub fn process() -> Result<Self, Errno> {
+        Self::get(RUSAGE_SELF)
+    }
+
+    /// Returns the sum of minor and major faults.
+    pub fn total(&self) -> u64 {
+        self.minor + self.major
+    }
+
+    fn since(&self, start: &Self) -> Self {
+        Self {
+            minor: self.minor - start.minor,
+            major: self.major - start.major,
+        }
+    }
+}
+
+/// Counts the page faults of the calling thread from its creation on.
+///
+/// With [FaultGuard::asserting], dropping the guard panics if the thread
+/// took a page fault, which lets tests prove that a code path runs without
+/// page faults, e.g. after `mlockall(MCL_CURRENT | MCL_FUTURE)` and
+/// [crate::mman::prefault_stack].
+///
+/// ```no_run
+/// use linux_rt::mman::FaultGuard;
+///
+/// let buffer = vec![0u8; 4096];
+/// let guard = FaultGuard::asserting().unwrap();
+/// let sum: u32 = buffer.iter().map(|&b| u32::from(b)).sum();
+/// drop(guard);
+/// # let _ = sum;
+/// ```
+#[derive(Debug)]
+pub struct FaultGuard {
+    start: PageFaults,
+    asserting: bool,
+    _thread: PhantomData<*const ()>,
+}
+
+impl FaultGuard {
+    /// Starts counting.
+    pub fn new() -> Result<Self, Errno> {
+        Ok(Self {
+            start: PageFaults::thread()?,
+            asserting: false,
+            _thread: PhantomData,
+        })
+    }
+
+    /// Starts counting and panics on drop if any page fault occurred.
+    pub fn asserting() -> Result<Self, Errno> {
+        Ok(Self {
+            asserting: true,
+            ..Self::new()?
+        })
+    }
+
+    /// Returns the page faults since the guard was created.
+    pub fn faults(&self) -> Result<PageFaults, Errno> {
+        Ok(PageFaults::thread()?.since(&self.start))
+    }
+
+    /// Stops counting and returns the page faults since the guard was
+    /// created, without asserting.
+    pub fn finish(mut self) -> Result<PageFaults, Errno> {
+        self.asserting = false;
+        self.faults()
+    }
+}
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        if !self.asserting || std::thread::panicking() {
+            return;
+        }
+        let faults = self.faults().expect("getrusage(RUSAGE_THREAD) failed");
+        assert!(
+            faults.total() == 0,
+            "page faults in a fault-free section: {} minor, {} major",
+            faults.minor,
+            faults.major
+        );
+    }
+}
+
+/// Runs `f` and returns its result with the page faults it took on the
+/// calling thread.
+pub fn count_page_faults<R>(f: impl FnOnce() -> R) -> Result<(R, PageFaults), Errno> {
+    let guard = FaultGuard::new()?;
+    let result = f();
+    Ok((result, guard.finish()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::lowlevel::mman::{
+        map::MAP_ANONYMOUS, mmap, munmap, MAP_PRIVATE, PROT_READ, PROT_WRITE,
+    };
+
+    fn touch_fresh_pages(pages: usize) {
+        let len = pages * crate::mman::page_size();
+        let prot = PROT_READ | PROT_WRITE;
+        let flags = MAP_PRIVATE | MAP_ANONYMOUS;
+        let addr = unsafe { mmap(std::ptr::null_mut(), len, prot, flags, -1, 0) }.unwrap();
+        for offset in (0..len).step_by(crate::mman::page_size()) {
+            unsafe { std::ptr::write_volatile((addr + offset) as *mut u8, 1) };
+        }
+        unsafe { munmap(addr as *mut _, len) }.unwrap();
+    }
+
+    #[test]
+    fn test_count_page_faults() {
+        let ((), faults) = count_page_faults(|| touch_fresh_pages(8)).unwrap();
+        assert!(faults.minor >= 8);
+        assert!(PageFaults::process().unwrap().total() >= faults.total());
+    }
+
+    #[test]
+    fn test_asserting() {
+        let buffer = vec![1u8; 4096];
+        let guard = FaultGuard::asserting().unwrap();
+        let sum: u64 = buffer.iter().map(|&b| u64::from(b)).sum();
+        drop(guard);
+        assert_eq!(sum, 4096);
+    }
+
+    #[test]
+    #[should_panic(expected = "page faults in a fault-free section")]
+    fn test_asserting_fault() {
+        let _guard = FaultGuard::asserting().unwrap();
+        touch_fresh_pages(1);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::BTreeMap,
+    ffi::c_void,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use syscalls::Errno;
+
+use crate::mman::{mlock, mlock2, munlock, page_size, Mlock2Flags};
+
+/// Non-overlapping intervals `start -> (end, count)` of the pages locked
+/// through [LockedRegion], with `count` the number of regions sharing them.
+type Intervals = BTreeMap<usize, (usize, usize)>;
+
+/// The kernel does not count locks, so a page is only unlocked once the last
+/// region containing it is dropped.
+static LOCKED: Mutex<Intervals> = Mutex::new(BTreeMap::new());
+
+fn locked() -> MutexGuard<'static, Intervals> {
+    LOCKED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Splits the interval containing `at` so that an interval starts at `at`.
+fn split(intervals: &mut Intervals, at: usize) {
+    if let Some((&start, &(end, count))) = intervals.range(..at).next_back() {
+        if end > at {
+            intervals.insert(start, (at, count));
+            intervals.insert(at, (end, count));
+        }
+    }
+}
+
+/// Returns the intervals overlapping `start..end`, split at the bounds.
+fn overlapping(intervals: &mut Intervals, start: usize, end: usize) -> Vec<(usize, usize)> {
+    split(intervals, start);
+    split(intervals, end);
+    intervals
+        .range(start..end)
+        .map(|(&s, &(e, _))| (s, e))
+        .collect()
+}
+
+fn acquire(intervals: &mut Intervals, start: usize, end: usize) {
+    let mut cursor = start;
+    for (s, e) in overlapping(intervals, start, end) {
+        if cursor < s {
+            intervals.insert(cursor, (s, 1));
+        }
+        intervals.get_mut(&s).unwrap().1 += 1;
+        cursor = e;
+    }
+    if cursor < end {
+        intervals.insert(cursor, (end, 1));
+    }
+}
+
+/// Releases `start..end` and returns the ranges that are no longer shared.
+fn release(intervals: &mut Intervals, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut unused: Vec<(usize, usize)> = Vec::new();
+    for (s, e) in overlapping(intervals, start, end) {
+        let count = &mut intervals.get_mut(&s).unwrap().1;
+        *count -= 1;
+        if *count > 0 {
+            continue;
+        }
+        intervals.remove(&s);
+        match unused.last_mut() {
+            Some(last) if last.1 == s => last.1 = e,
+            _ => unused.push((s, e)),
+        }
+    }
+    unused
+}
+
+/// A page-aligned range of memory locked into RAM, unlocked on drop.
+///
+/// Locks are counted per page across all regions, so overlapping regions
+/// and regions sharing a page can be dropped in any order. The flags of the
+/// most recent lock apply to shared pages. Locks made with [mlock] or
+/// [crate::mman::mlockall] directly are not tracked.
+#[derive(Debug)]
+pub struct LockedRegion<'a> {
+    start: usize,
+    end: usize,
+    _memory: PhantomData<&'a [u8]>,
+}
+
+impl<'a> LockedRegion<'a> {
+    /// Locks all pages containing a part of the `len` bytes at `addr`.
+    ///
+    /// # Safety
+    /// The memory must stay mapped for the lifetime `'a`.
+    pub unsafe fn new(addr: *const c_void, len: usize, flags: Mlock2Flags) -> Result<Self, Errno> {
+        let page_size = page_size();
+        let start = addr as usize & !(page_size - 1);
+        let end = if len == 0 {
+            start
+        } else {
+            (addr as usize)
+                .checked_add(len)
+                .and_then(|end| end.checked_next_multiple_of(page_size))
+                .ok_or(Errno::EINVAL)?
+        };
+        if start < end {
+            let mut intervals = locked();
+            let (addr, len) = (start as *const c_void, end - start);
+            if flags.is_empty() {
+                mlock(addr, len)?;
+            } else {
+                mlock2(addr, len, flags)?;
+            }
+            acquire(&mut intervals, start, end);
+        }
+        Ok(Self {
+            start,
+            end,
+            _memory: PhantomData,
+        })
+    }
+
+    /// Returns the page-aligned start of the region.
+    pub fn addr(&self) -> *const c_void {
+        self.start as *const c_void
+    }
+
+    /// Returns the length of the region in bytes, a multiple of the page size.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns `true` if no page is locked.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl Drop for LockedRegion<'_> {
+    fn drop(&mut self) {
+        if self.is_empty() {
+            return;
+        }
+        let mut intervals = locked();
+        for (start, end) in release(&mut intervals, self.start, self.end) {
+            let _ = unsafe { munlock(start as *const c_void, end - start) };
+        }
+    }
+}
+
+/// Locks the pages of `slice` into RAM until the returned region is dropped.
+pub fn lock_slice<T>(slice: &[T]) -> Result<LockedRegion<'_>, Errno> {
+    lock_slice_with(slice, Mlock2Flags::empty())
+}
+
+/// Like [lock_slice] but with [Mlock2Flags].
+pub fn lock_slice_with<T>(slice: &[T], flags: Mlock2Flags) -> Result<LockedRegion<'_>, Errno> {
+    unsafe { LockedRegion::new(slice.as_ptr().cast(), size_of_val(slice), flags) }
+}
+
+/// A [Box] whose memory is locked into RAM. It dereferences to `T`.
+#[derive(Debug)]
+pub struct LockedBox<T: ?Sized> {
+    // Declared first to unlock before the memory is freed.
+    region: LockedRegion<'static>,
+    value: Box<T>,
+}
+
+impl<T: ?Sized> LockedBox<T> {
+    /// Returns the locked region.
+    pub fn region(&self) -> &LockedRegion<'static> {
+        &self.region
+    }
+
+    /// Unlocks the memory and returns the [Box].
+    pub fn into_inner(self) -> Box<T> {
+        let LockedBox { region, value } = self;
+        drop(region);
+        value
+    }
+}
+
+impl<T: ?Sized> Deref for LockedBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: ?Sized> DerefMut for LockedBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// Locks the heap memory of `value` into RAM.
+pub fn lock_box<T: ?Sized>(value: Box<T>) -> Result<LockedBox<T>, Errno> {
+    // Moving the box does not move its heap memory, which is freed only
+    // after the region has been dropped.
+    let region = unsafe {
+        LockedRegion::new(
+            (&*value as *const T).cast(),
+            size_of_val(&*value),
+            Mlock2Flags::empty(),
+        )
+    }?;
+    Ok(LockedBox { region, value })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn count(addr: usize) -> usize {
+        let intervals = locked();
+        match intervals.range(..=addr).next_back() {
+            Some((_, &(end, count))) if end > addr => count,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_intervals() {
+        let mut intervals = Intervals::new();
+        acquire(&mut intervals, 0x1000, 0x4000);
+        acquire(&mut intervals, 0x3000, 0x6000);
+        assert_eq!(
+            intervals.clone().into_iter().collect::<Vec<_>>(),
+            [
+                (0x1000, (0x3000, 1)),
+                (0x3000, (0x4000, 2)),
+                (0x4000, (0x6000, 1))
+            ]
+        );
+        assert_eq!(release(&mut intervals, 0x1000, 0x4000), [(0x1000, 0x3000)]);
+        acquire(&mut intervals, 0x2000, 0x7000);
+        assert_eq!(release(&mut intervals, 0x3000, 0x6000), []);
+        assert_eq!(release(&mut intervals, 0x2000, 0x7000), [(0x2000, 0x7000)]);
+        assert!(intervals.is_empty());
+    }
+
+    #[test]
+    fn test_lock_slice() {
+        let page_size = page_size();
+        let buffer = vec![0u8; 3 * page_size];
+        let middle = buffer.as_ptr() as usize + page_size;
+
+        let first = lock_slice(&buffer[..2 * page_size]).unwrap();
+        assert_eq!(first.addr() as usize % page_size, 0);
+        assert!(first.len() >= 2 * page_size);
+        let second = lock_slice_with(&buffer[page_size..], Mlock2Flags::MLOCK_ONFAULT).unwrap();
+        assert_eq!(count(middle), 2);
+        drop(first);
+        assert_eq!(count(middle), 1);
+        drop(second);
+        assert_eq!(count(middle), 0);
+
+        assert!(lock_slice::<u8>(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lock_box() {
+        let mut locked = lock_box(vec![1u64; 1024].into_boxed_slice()).unwrap();
+        locked[3] = 4;
+        let addr = locked.as_ptr() as usize;
+        assert_eq!(count(addr), 1);
+        let value = locked.into_inner();
+        assert_eq!(value[3], 4);
+        assert_eq!(count(addr), 0);
+    }
+}
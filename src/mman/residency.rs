@@ -0,0 +1,280 @@
+use std::{ffi::c_void, fmt, fs};
+
+use syscalls::Errno;
+
+use crate::{
+    lowlevel::{io_errno, mman::mincore as raw_mincore},
+    mman::page_size,
+};
+
+fn read_proc(path: &str) -> Result<String, Errno> {
+    fs::read_to_string(path).map_err(io_errno)
+}
+
+/// Returns the residency of each page of the page-aligned range containing
+/// the `len` bytes at `addr`. Fails with `ENOMEM` if the range is not mapped.
+pub(crate) fn page_residency(addr: usize, len: usize) -> Result<Vec<bool>, Errno> {
+    let page_size = page_size();
+    let start = addr & !(page_size - 1);
+    let end = addr
+        .checked_add(len)
+        .and_then(|end| end.checked_next_multiple_of(page_size))
+        .ok_or(Errno::EINVAL)?;
+    let mut vec = vec![0u8; (end - start) / page_size];
+    unsafe { raw_mincore(start as *mut c_void, end - start, vec.as_mut_ptr()) }?;
+    Ok(vec.into_iter().map(|page| page & 1 == 1).collect())
+}
+
+/// Returns for each page containing a part of `slice` whether it is
+/// resident in RAM, using `mincore`.
+pub fn mincore<T>(slice: &[T]) -> Result<Vec<bool>, Errno> {
+    if size_of_val(slice) == 0 {
+        return Ok(Vec::new());
+    }
+    page_residency(slice.as_ptr() as usize, size_of_val(slice))
+}
+
+/// Returns `true` if all pages containing a part of `slice` are resident.
+pub fn is_resident<T>(slice: &[T]) -> Result<bool, Errno> {
+    Ok(mincore(slice)?.into_iter().all(|resident| resident))
+}
+
+fn parse_kb(value: &str) -> Option<u64> {
+    Some(
+        value
+            .trim()
+            .strip_suffix("kB")?
+            .trim()
+            .parse::<u64>()
+            .ok()?
+            * 1024,
+    )
+}
+
+/// Memory usage of the calling process from `/proc/self/status`, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryStatus {
+    /// Resident set size (`VmRSS`).
+    pub vm_rss: u64,
+    /// Locked memory (`VmLck`).
+    pub vm_lck: u64,
+    /// Pinned memory (`VmPin`), e.g. used for DMA.
+    pub vm_pin: u64,
+}
+
+impl MemoryStatus {
+    /// Reads the memory status of the calling process.
+    pub fn current() -> Result<Self, Errno> {
+        Self::parse(&read_proc("/proc/self/status")?)
+    }
+
+    /// Parses the contents of `/proc/<pid>/status`. Fails with `EINVAL` if a
+    /// field is missing.
+    pub fn parse(status: &str) -> Result<Self, Errno> {
+        let field = |name: &str| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .and_then(parse_kb)
+                .ok_or(Errno::EINVAL)
+        };
+        Ok(Self {
+            vm_rss: field("VmRSS")?,
+            vm_lck: field("VmLck")?,
+            vm_pin: field("VmPin")?,
+        })
+    }
+}
+
+/// A mapping of the calling process from `/proc/self/smaps`. Sizes are in
+/// bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Mapping {
+    /// Start address.
+    pub start: usize,
+    /// End address, exclusive.
+    pub end: usize,
+    /// Permissions, e.g. `rw-p`.
+    pub perms: String,
+    /// Backing file or pseudo path such as `[heap]`. Empty for anonymous
+    /// mappings.
+    pub pathname: String,
+    /// Size of the mapping.
+    pub size: u64,
+    /// Resident part of the mapping.
+    pub rss: u64,
+    /// Locked part of the mapping. Pages shared with other processes are
+    /// accounted proportionally.
+    pub locked: u64,
+    /// Two-letter flags of the kernel, e.g. `lo` for locked.
+    pub vm_flags: Vec<String>,
+}
+
+impl Mapping {
+    fn has_flag(&self, flag: &str) -> bool {
+        self.vm_flags.iter().any(|f| f == flag)
+    }
+
+    /// Returns `true` if the mapping is marked as locked (`VM_LOCKED`).
+    pub fn is_locked(&self) -> bool {
+        self.has_flag("lo")
+    }
+
+    /// Returns `true` if the whole mapping is resident.
+    pub fn is_resident(&self) -> bool {
+        self.rss >= self.size
+    }
+
+    /// Returns `false` for mappings that `mlock` skips: inaccessible guard
+    /// mappings, I/O and PFN mappings such as `[vvar]`, and special
+    /// mappings such as `[vdso]` and `[vsyscall]`.
+    pub fn is_lockable(&self) -> bool {
+        !self.perms.starts_with("---")
+            && !["io", "pf", "de", "mm"]
+                .iter()
+                .any(|flag| self.has_flag(flag))
+            && self.pathname != "[vsyscall]"
+    }
+
+    fn parse(smaps: &str) -> Vec<Mapping> {
+        let mut mappings: Vec<Mapping> = Vec::new();
+        for line in smaps.lines() {
+            let mut fields = line.split_whitespace();
+            let Some(first) = fields.next() else {
+                continue;
+            };
+            if let Some((start, end)) = first.split_once('-') {
+                if let (Ok(start), Ok(end)) = (
+                    usize::from_str_radix(start, 16),
+                    usize::from_str_radix(end, 16),
+                ) {
+                    let perms = fields.next().unwrap_or_default().to_string();
+                    mappings.push(Mapping {
+                        start,
+                        end,
+                        perms,
+                        pathname: fields.skip(3).collect::<Vec<_>>().join(" "),
+                        ..Default::default()
+                    });
+                    continue;
+                }
+            }
+            let Some(mapping) = mappings.last_mut() else {
+                continue;
+            };
+            let value = line[first.len()..].trim();
+            match first {
+                "Size:" => mapping.size = parse_kb(value).unwrap_or_default(),
+                "Rss:" => mapping.rss = parse_kb(value).unwrap_or_default(),
+                "Locked:" => mapping.locked = parse_kb(value).unwrap_or_default(),
+                "VmFlags:" => mapping.vm_flags = fields.map(str::to_string).collect(),
+                _ => {}
+            }
+        }
+        mappings
+    }
+}
+
+impl fmt::Display for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:x}-{:x} {} {} (size {} kB, rss {} kB, locked {} kB{})",
+            self.start,
+            self.end,
+            self.perms,
+            self.pathname,
+            self.size / 1024,
+            self.rss / 1024,
+            self.locked / 1024,
+            if self.is_locked() { "" } else { ", not locked" }
+        )
+    }
+}
+
+/// Returns the mappings of the calling process from `/proc/self/smaps`.
+pub fn mappings() -> Result<Vec<Mapping>, Errno> {
+    Ok(Mapping::parse(&read_proc("/proc/self/smaps")?))
+}
+
+/// Returns the lockable mappings that are not locked or not fully resident.
+///
+/// After `mlockall(MCL_CURRENT)` the result is expected to be empty, so a
+/// real-time process can fail at startup instead of taking page faults later.
+/// With `MCL_ONFAULT`, locked mappings may legitimately be partly resident.
+pub fn unlocked_mappings() -> Result<Vec<Mapping>, Errno> {
+    Ok(mappings()?
+        .into_iter()
+        .filter(|m| m.is_lockable() && !(m.is_locked() && m.is_resident()))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SMAPS: &str = "\
+55d0c0a00000-55d0c0a21000 rw-p 00000000 00:00 0                          [heap]
+Size:                132 kB
+Rss:                 132 kB
+Locked:              132 kB
+VmFlags: rd wr mr mw me lo ac
+7f7aa4021000-7f7aa4022000 ---p 00000000 00:00 0
+Size:                  4 kB
+Rss:                   0 kB
+Locked:                0 kB
+VmFlags: mr mw me nr
+7f8c8a255000-7f8c8a27b000 r--p 00000000 fe:00 395379                     /usr/lib/my lib.so
+Size:                152 kB
+Rss:                 100 kB
+Locked:                0 kB
+VmFlags: rd mr mw me
+7f7aac4d9000-7f7aac4dd000 r--p 00000000 00:00 0                          [vvar]
+Size:                 16 kB
+Rss:                   0 kB
+Locked:                0 kB
+VmFlags: rd mr pf io de dd
+";
+
+    #[test]
+    fn test_parse_smaps() {
+        let mappings = Mapping::parse(SMAPS);
+        assert_eq!(mappings.len(), 4);
+        assert_eq!(mappings[0].pathname, "[heap]");
+        assert_eq!(mappings[0].size, 132 * 1024);
+        assert!(mappings[0].is_locked() && mappings[0].is_resident());
+        assert!(!mappings[1].is_lockable());
+        assert_eq!(mappings[2].pathname, "/usr/lib/my lib.so");
+        assert_eq!(
+            (mappings[2].start, mappings[2].end),
+            (0x7f8c8a255000, 0x7f8c8a27b000)
+        );
+        assert!(mappings[2].is_lockable() && !mappings[2].is_locked());
+        assert!(!mappings[3].is_lockable());
+    }
+
+    #[test]
+    fn test_memory_status() {
+        let status = MemoryStatus::parse("VmLck:\t  70656 kB\nVmPin:\t 0 kB\nVmRSS:\t 5252 kB\n");
+        assert_eq!(
+            status,
+            Ok(MemoryStatus {
+                vm_rss: 5252 * 1024,
+                vm_lck: 70656 * 1024,
+                vm_pin: 0,
+            })
+        );
+        assert_eq!(MemoryStatus::parse("VmRSS: 1 kB"), Err(Errno::EINVAL));
+        assert!(MemoryStatus::current().unwrap().vm_rss > 0);
+    }
+
+    #[test]
+    fn test_mincore() {
+        let buffer = vec![1u8; 4 * page_size()];
+        assert!(is_resident(&buffer).unwrap());
+        assert!(mincore(&buffer).unwrap().len() >= 4);
+        assert!(mincore::<u8>(&[]).unwrap().is_empty());
+        assert!(!mappings().unwrap().is_empty());
+        unlocked_mappings().unwrap();
+    }
+}
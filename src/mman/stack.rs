@@ -0,0 +1,166 @@
+use std::{fs, hint::black_box, ptr};
+
+use syscalls::Errno;
+
+use crate::{
+    lowlevel::{
+        io_errno,
+        resource::{prlimit64, Rlimit64, RLIMIT_STACK, RLIM_INFINITY},
+    },
+    mman::{page_size, residency::page_residency},
+};
+
+/// Default `stack_guard_gap` of the kernel in pages, kept free below a
+/// growing stack.
+const STACK_GUARD_GAP: usize = 256;
+
+/// Bounds of the stack of the calling thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StackBounds {
+    /// Lowest address the stack may grow down to.
+    low: usize,
+    /// End of the stack mapping.
+    high: usize,
+}
+
+fn parse_range(line: &str) -> Option<(usize, usize)> {
+    let (start, end) = line.split_whitespace().next()?.split_once('-')?;
+    Some((
+        usize::from_str_radix(start, 16).ok()?,
+        usize::from_str_radix(end, 16).ok()?,
+    ))
+}
+
+/// Finds the stack mapping containing `sp` in `/proc/self/maps`.
+///
+/// Thread stacks are fixed-size mappings above a guard page. The stack of
+/// the main thread, `[stack]`, grows down on demand up to `RLIMIT_STACK` and
+/// the guard gap above the next lower mapping.
+fn stack_bounds(maps: &str, sp: usize, stack_limit: u64) -> Result<StackBounds, Errno> {
+    let mut previous_end = 0;
+    for line in maps.lines() {
+        let Some((start, end)) = parse_range(line) else {
+            continue;
+        };
+        if !(start..end).contains(&sp) {
+            previous_end = end;
+            continue;
+        }
+        if !line.ends_with("[stack]") {
+            return Ok(StackBounds {
+                low: start,
+                high: end,
+            });
+        }
+        let gap = previous_end.saturating_add(STACK_GUARD_GAP * page_size());
+        let limit = match stack_limit {
+            RLIM_INFINITY => 0,
+            limit => end.saturating_sub(usize::try_from(limit).unwrap_or(usize::MAX)),
+        };
+        return Ok(StackBounds {
+            low: gap.max(limit).min(start),
+            high: end,
+        });
+    }
+    Err(Errno::EFAULT)
+}
+
+/// Writes every page of `low..high` back with its own value.
+#[inline(never)]
+fn touch(low: usize, high: usize) {
+    let page_size = page_size();
+    for page in (low..high).step_by(page_size) {
+        let page = page as *mut u8;
+        // The pages may already hold live data, so their contents are
+        // preserved. The volatile accesses cannot be elided.
+        unsafe { ptr::write_volatile(page, ptr::read_volatile(page)) };
+    }
+}
+
+/// Faults in the stack of the calling thread down to `bytes` below the
+/// current stack pointer, so that later calls up to that depth take no page
+/// faults. Combine with `mlockall(MCL_CURRENT | MCL_FUTURE)` to keep the pages
+/// resident. It works on the main thread and on already running threads.
+///
+/// Returns the number of bytes from the lowest prefaulted page up to the
+/// current stack pointer. Fails with `ENOMEM` if the stack cannot grow that
+/// deep and with `EFAULT` if the pages are not resident afterwards.
+#[inline(never)]
+pub fn prefault_stack(bytes: usize) -> Result<usize, Errno> {
+    let marker = 0u8;
+    let sp = black_box(&marker) as *const u8 as usize;
+    let page_size = page_size();
+
+    let maps = fs::read_to_string("/proc/self/maps").map_err(io_errno)?;
+    let mut limit = Rlimit64::default();
+    unsafe { prlimit64(0, RLIMIT_STACK, ptr::null(), &mut limit) }?;
+    let bounds = stack_bounds(&maps, sp, limit.rlim_cur)?;
+
+    let low = sp.checked_sub(bytes).ok_or(Errno::ENOMEM)? & !(page_size - 1);
+    if low < bounds.low {
+        return Err(Errno::ENOMEM);
+    }
+    let high = (sp & !(page_size - 1)) + page_size;
+    touch(low, high);
+    if !page_residency(low, high - low)?
+        .into_iter()
+        .all(|page| page)
+    {
+        return Err(Errno::EFAULT);
+    }
+    Ok(sp - low)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stack_bounds() {
+        let page_size = page_size();
+        let gap = STACK_GUARD_GAP * page_size;
+        let maps = "\
+7f0000000000-7f0000001000 ---p 00000000 00:00 0
+7f0000001000-7f0000801000 rw-p 00000000 00:00 0
+7ffd00000000-7ffd00021000 rw-p 00000000 00:00 0                          [stack]
+";
+        assert_eq!(
+            stack_bounds(maps, 0x7f0000400000, 8 << 20),
+            Ok(StackBounds {
+                low: 0x7f0000001000,
+                high: 0x7f0000801000
+            })
+        );
+        assert_eq!(
+            stack_bounds(maps, 0x7ffd00020000, 8 << 20),
+            Ok(StackBounds {
+                low: 0x7ffd00021000 - (8 << 20),
+                high: 0x7ffd00021000
+            })
+        );
+        assert_eq!(
+            stack_bounds(maps, 0x7ffd00020000, RLIM_INFINITY),
+            Ok(StackBounds {
+                low: 0x7f0000801000 + gap,
+                high: 0x7ffd00021000
+            })
+        );
+        assert_eq!(stack_bounds(maps, 0x1000, 8 << 20), Err(Errno::EFAULT));
+    }
+
+    #[test]
+    fn test_prefault_stack() {
+        let bytes = prefault_stack(64 * 1024).unwrap();
+        assert!(bytes >= 64 * 1024);
+
+        std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                prefault_stack(128 * 1024).unwrap();
+                assert_eq!(prefault_stack(1 << 30), Err(Errno::ENOMEM));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
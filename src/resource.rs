@@ -0,0 +1,308 @@
+use std::{fmt, ptr, time::Duration};
+
+use syscalls::Errno;
+
+use crate::{
+    lowlevel::{
+        capability::{has_effective, CAP_IPC_LOCK, CAP_SYS_NICE},
+        resource::{
+            prlimit64, Rlimit64, RLIMIT_MEMLOCK, RLIMIT_NICE, RLIMIT_RTPRIO, RLIMIT_RTTIME,
+            RLIM_INFINITY,
+        },
+    },
+    mman::MemoryStatus,
+    sched::{get_attr, Attributes, Pid, Policy},
+};
+
+/// Resource limits relevant for real-time processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    /// Maximum number of bytes of memory that may be locked into RAM.
+    Memlock,
+    /// Ceiling for raising the nice value, as `20 - nice`.
+    Nice,
+    /// Ceiling for the real-time priority of `Fifo` and `RoundRobin`.
+    Rtprio,
+    /// Limit in microseconds on the CPU time a real-time thread may
+    /// consume without a blocking system call. `SIGXCPU` is sent when the
+    /// soft limit is reached and `SIGKILL` at the hard limit.
+    Rttime,
+}
+
+impl Resource {
+    /// Converts a [Resource] into a raw `RLIMIT_*` value.
+    pub fn as_raw(&self) -> u32 {
+        match self {
+            Resource::Memlock => RLIMIT_MEMLOCK,
+            Resource::Nice => RLIMIT_NICE,
+            Resource::Rtprio => RLIMIT_RTPRIO,
+            Resource::Rttime => RLIMIT_RTTIME,
+        }
+    }
+}
+
+/// Soft and hard limit of a resource. `None` means unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Limit<T = u64> {
+    /// The value enforced by the kernel.
+    pub soft: Option<T>,
+    /// The ceiling for the soft limit. Only privileged processes may raise it.
+    pub hard: Option<T>,
+}
+
+impl Limit {
+    fn from_raw(raw: Rlimit64) -> Self {
+        let value = |v| (v != RLIM_INFINITY).then_some(v);
+        Self {
+            soft: value(raw.rlim_cur),
+            hard: value(raw.rlim_max),
+        }
+    }
+
+    fn as_raw(&self) -> Rlimit64 {
+        Rlimit64 {
+            rlim_cur: self.soft.unwrap_or(RLIM_INFINITY),
+            rlim_max: self.hard.unwrap_or(RLIM_INFINITY),
+        }
+    }
+
+    /// Returns `true` if `value` does not exceed the soft limit.
+    pub fn permits(&self, value: u64) -> bool {
+        self.soft.is_none_or(|soft| value <= soft)
+    }
+}
+
+/// Gets the limit of `resource` of the process `pid` and replaces it with
+/// `new` if given. Returns the previous limit.
+///
+/// Setting the limit of another process requires `CAP_SYS_RESOURCE` or
+/// matching credentials.
+pub fn prlimit(pid: Pid, resource: Resource, new: Option<Limit>) -> Result<Limit, Errno> {
+    let new = new.map(|limit| limit.as_raw());
+    let new_ptr = new
+        .as_ref()
+        .map_or(ptr::null(), |new| new as *const Rlimit64);
+    let mut old = Rlimit64::default();
+    unsafe { prlimit64(pid.as_raw(), resource.as_raw(), new_ptr, &mut old) }?;
+    Ok(Limit::from_raw(old))
+}
+
+/// Returns the limit of `resource` of the calling process.
+pub fn get_limit(resource: Resource) -> Result<Limit, Errno> {
+    prlimit(Pid::this(), resource, None)
+}
+
+/// Sets the limit of `resource` of the calling process. Raising the hard
+/// limit requires `CAP_SYS_RESOURCE`.
+pub fn set_limit(resource: Resource, limit: Limit) -> Result<(), Errno> {
+    prlimit(Pid::this(), resource, Some(limit)).and(Ok(()))
+}
+
+/// Returns the [Resource::Rttime] limit of the calling process.
+pub fn get_rttime_limit() -> Result<Limit<Duration>, Errno> {
+    let limit = get_limit(Resource::Rttime)?;
+    Ok(Limit {
+        soft: limit.soft.map(Duration::from_micros),
+        hard: limit.hard.map(Duration::from_micros),
+    })
+}
+
+/// Sets the [Resource::Rttime] limit of the calling process to protect
+/// against runaway real-time threads. The limits are truncated to
+/// microseconds.
+pub fn set_rttime_limit(limit: Limit<Duration>) -> Result<(), Errno> {
+    let micros = |d: Duration| u64::try_from(d.as_micros()).map_err(|_| Errno::EINVAL);
+    set_limit(
+        Resource::Rttime,
+        Limit {
+            soft: limit.soft.map(micros).transpose()?,
+            hard: limit.hard.map(micros).transpose()?,
+        },
+    )
+}
+
+/// The verdict of a pre-flight check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preflight {
+    /// Permitted because the caller has the capability overriding the limit.
+    Privileged,
+    /// Permitted by the soft limit.
+    WithinLimit,
+    /// Denied because `requested` exceeds the soft limit of `resource`.
+    /// The soft limit can be raised up to `limit.hard` without privileges.
+    ExceedsLimit {
+        /// The limiting resource.
+        resource: Resource,
+        /// The requested value in units of the resource.
+        requested: u64,
+        /// The current limit.
+        limit: Limit,
+    },
+    /// Denied because only a privileged process may do it, independent of
+    /// resource limits.
+    RequiresCapability(&'static str),
+}
+
+impl Preflight {
+    /// Returns `true` if the request is permitted.
+    pub fn is_permitted(&self) -> bool {
+        matches!(self, Preflight::Privileged | Preflight::WithinLimit)
+    }
+}
+
+impl fmt::Display for Preflight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Preflight::Privileged => f.write_str("permitted by capability"),
+            Preflight::WithinLimit => f.write_str("permitted by resource limit"),
+            Preflight::ExceedsLimit {
+                resource,
+                requested,
+                limit,
+            } => {
+                write!(f, "{requested} exceeds the soft limit ")?;
+                match limit.soft {
+                    Some(soft) => write!(f, "{soft}")?,
+                    None => f.write_str("unlimited")?,
+                }
+                write!(f, " of {resource:?}")?;
+                match limit.hard {
+                    Some(hard) if *requested <= hard => write!(f, ", raise it up to {hard}"),
+                    Some(hard) => write!(f, " and the hard limit {hard}"),
+                    None => f.write_str(", raise it"),
+                }
+            }
+            Preflight::RequiresCapability(cap) => write!(f, "requires {cap}"),
+        }
+    }
+}
+
+fn check_limit(resource: Resource, requested: u64) -> Result<Preflight, Errno> {
+    let limit = get_limit(resource)?;
+    Ok(if limit.permits(requested) {
+        Preflight::WithinLimit
+    } else {
+        Preflight::ExceedsLimit {
+            resource,
+            requested,
+            limit,
+        }
+    })
+}
+
+/// Checks whether the calling thread may set the scheduling attributes
+/// `attr` with [crate::sched::set_attr]. Fails with `EINVAL` if the priority
+/// is invalid for the policy.
+pub fn check_attributes(attr: &Attributes) -> Result<Preflight, Errno> {
+    let real_time = matches!(attr.policy, Policy::Fifo | Policy::RoundRobin);
+    if real_time != (1..=99).contains(&attr.priority) {
+        return Err(Errno::EINVAL);
+    }
+    if has_effective(CAP_SYS_NICE)? {
+        return Ok(Preflight::Privileged);
+    }
+    let current = get_attr(Pid::this())?;
+    match attr.policy {
+        Policy::Fifo | Policy::RoundRobin => {
+            // Lowering the priority of a real-time thread is always allowed.
+            let real_time = matches!(current.policy, Policy::Fifo | Policy::RoundRobin);
+            if real_time && attr.priority <= current.priority {
+                return Ok(Preflight::WithinLimit);
+            }
+            check_limit(Resource::Rtprio, attr.priority.into())
+        }
+        Policy::Deadline => Ok(Preflight::RequiresCapability("CAP_SYS_NICE")),
+        Policy::Normal | Policy::Batch | Policy::Idle | Policy::Ext => {
+            if attr.nice >= current.nice {
+                return Ok(Preflight::WithinLimit);
+            }
+            let requested = u64::try_from(20 - attr.nice).map_err(|_| Errno::EINVAL)?;
+            check_limit(Resource::Nice, requested)
+        }
+    }
+}
+
+/// Checks whether the calling process may lock another `bytes` bytes into
+/// RAM, in addition to the memory it has locked already.
+pub fn check_lock(bytes: usize) -> Result<Preflight, Errno> {
+    if has_effective(CAP_IPC_LOCK)? {
+        return Ok(Preflight::Privileged);
+    }
+    let locked = MemoryStatus::current()?.vm_lck;
+    check_limit(Resource::Memlock, locked + bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sched::SchedFlags;
+
+    #[test]
+    fn test_limits() {
+        let limit = get_limit(Resource::Memlock).unwrap();
+        set_limit(Resource::Memlock, limit).unwrap();
+        assert_eq!(prlimit(Pid::this(), Resource::Memlock, None), Ok(limit));
+
+        let rttime = get_rttime_limit().unwrap();
+        set_rttime_limit(rttime).unwrap();
+        assert_eq!(get_rttime_limit(), Ok(rttime));
+    }
+
+    #[test]
+    fn test_preflight() {
+        let mut attr = Attributes {
+            policy: Policy::Fifo,
+            flags: SchedFlags::empty(),
+            nice: 0,
+            priority: 100,
+            runtime_ns: 0,
+            deadline_ns: 0,
+            period_ns: 0,
+            sched_util_min: 0,
+            sched_util_max: 0,
+        };
+        assert_eq!(check_attributes(&attr), Err(Errno::EINVAL));
+
+        attr.priority = 50;
+        let verdict = check_attributes(&attr).unwrap();
+        if has_effective(CAP_SYS_NICE).unwrap() {
+            assert_eq!(verdict, Preflight::Privileged);
+        } else {
+            let limit = get_limit(Resource::Rtprio).unwrap();
+            assert_eq!(verdict.is_permitted(), limit.permits(50));
+        }
+
+        let verdict = check_lock(4096).unwrap();
+        if has_effective(CAP_IPC_LOCK).unwrap() {
+            assert_eq!(verdict, Preflight::Privileged);
+        }
+    }
+
+    #[test]
+    fn test_permits() {
+        let limit = Limit {
+            soft: Some(8),
+            hard: None,
+        };
+        assert!(limit.permits(8));
+        assert!(!limit.permits(9));
+        assert!(Limit::default().permits(u64::MAX));
+    }
+
+    #[test]
+    fn test_display() {
+        let verdict = Preflight::ExceedsLimit {
+            resource: Resource::Rtprio,
+            requested: 80,
+            limit: Limit {
+                soft: Some(0),
+                hard: Some(99),
+            },
+        };
+        assert!(!verdict.is_permitted());
+        assert_eq!(
+            verdict.to_string(),
+            "80 exceeds the soft limit 0 of Rtprio, raise it up to 99"
+        );
+    }
+}
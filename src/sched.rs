@@ -7,8 +7,16 @@ use bitflags::bitflags;
 use std::{ffi::c_int, fmt::Error, mem};
 use syscalls::Errno;
 
+mod slack;
+mod thread;
+
+pub use slack::{get_timer_slack, set_timer_slack, TimerSlackGuard};
+pub use thread::ThreadConfig;
+
 /// Currently, Linux supports the scheduling policies defined in this enum.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Policy {
     ///The standard round-robin time-sharing policy
     Normal,
@@ -66,6 +74,7 @@ impl Policy {
 bitflags! {
     /// These flags control the scheduling behavior
     #[derive(Debug, Clone, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct SchedFlags: std::ffi::c_short {
         /// Children created by fork(2) do not inherit
         /// privileged scheduling policies. See sched(7) for
@@ -107,6 +116,7 @@ bitflags! {
 
 ///Structure containing the scheduling policy and attributes for the specified thread.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attributes {
     /// This field specifies the scheduling policy, as one of the values of the enum.
     pub policy: Policy,
@@ -124,12 +134,15 @@ pub struct Attributes {
     pub priority: u32,
     /// This field specifies the "Runtime" parameter for deadline scheduling. The value is
     /// expressed in nanoseconds. This field is used only for the `Deadline` `policy`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::nanoseconds"))]
     pub runtime_ns: u64,
     /// This field specifies the "Deadline" parameter for deadline scheduling. The value
     /// is expressed in nanoseconds. This field is used only for the `Deadline` `policy`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::nanoseconds"))]
     pub deadline_ns: u64,
     /// This field specifies the "Period" parameter for deadline scheduling. The value is
     /// expressed in nanoseconds. This field is used only for the `Deadline` `policy`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::nanoseconds"))]
     pub period_ns: u64,
 
     /// These fields specify the expected minimum and maximum utilization, respectively. They are ignored
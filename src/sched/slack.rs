@@ -0,0 +1,105 @@
+use std::fs;
+
+use syscalls::Errno;
+
+use crate::{
+    lowlevel::{
+        io_errno,
+        prctl::{prctl, PR_GET_TIMERSLACK, PR_SET_TIMERSLACK},
+    },
+    sched::Pid,
+};
+
+fn timerslack_path(pid: Pid) -> String {
+    format!("/proc/{}/timerslack_ns", pid.as_raw())
+}
+
+/// Returns the timer slack of the thread `pid` in nanoseconds.
+///
+/// The calling thread ([Pid::this]) is queried with `prctl(PR_GET_TIMERSLACK)`,
+/// other threads through `/proc/<pid>/timerslack_ns`.
+pub fn get_timer_slack(pid: Pid) -> Result<u64, Errno> {
+    if pid == Pid::this() {
+        let slack = unsafe { prctl(PR_GET_TIMERSLACK, 0, 0, 0, 0) }?;
+        return Ok(slack as u64);
+    }
+    fs::read_to_string(timerslack_path(pid))
+        .map_err(io_errno)?
+        .trim()
+        .parse()
+        .map_err(|_| Errno::EINVAL)
+}
+
+/// Sets the timer slack of the thread `pid` to `slack_ns` nanoseconds,
+/// the amount by which the kernel may delay the expiry of its timers to
+/// group wakeups. 0 restores the default slack of the thread.
+///
+/// Setting the slack of another thread requires `CAP_SYS_NICE`. The slack of
+/// threads running with a real-time policy is 0 and cannot be changed since
+/// Linux 6.7.
+pub fn set_timer_slack(pid: Pid, slack_ns: u64) -> Result<(), Errno> {
+    if pid == Pid::this() {
+        // c_ulong is 32 bits wide on 32-bit targets.
+        #[allow(clippy::useless_conversion)]
+        let slack_ns = slack_ns.try_into().map_err(|_| Errno::EINVAL)?;
+        return unsafe { prctl(PR_SET_TIMERSLACK, slack_ns, 0, 0, 0) }.and(Ok(()));
+    }
+    fs::write(timerslack_path(pid), slack_ns.to_string()).map_err(io_errno)
+}
+
+/// Changes the timer slack of a thread and restores the previous value
+/// when dropped.
+#[derive(Debug)]
+pub struct TimerSlackGuard {
+    pid: Pid,
+    previous: u64,
+}
+
+impl TimerSlackGuard {
+    /// Sets the timer slack of the thread `pid` to `slack_ns`, see [set_timer_slack].
+    pub fn new(pid: Pid, slack_ns: u64) -> Result<Self, Errno> {
+        let previous = get_timer_slack(pid)?;
+        set_timer_slack(pid, slack_ns)?;
+        Ok(Self { pid, previous })
+    }
+
+    /// Returns the timer slack that is restored on drop.
+    pub fn previous(&self) -> u64 {
+        self.previous
+    }
+}
+
+impl Drop for TimerSlackGuard {
+    fn drop(&mut self) {
+        let _ = set_timer_slack(self.pid, self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syscalls::{syscall, Sysno};
+
+    use super::*;
+
+    #[test]
+    fn test_timer_slack() {
+        let default = get_timer_slack(Pid::this()).unwrap();
+        {
+            let guard = TimerSlackGuard::new(Pid::this(), 1).unwrap();
+            assert_eq!(guard.previous(), default);
+            assert_eq!(get_timer_slack(Pid::this()).unwrap(), 1);
+        }
+        assert_eq!(get_timer_slack(Pid::this()).unwrap(), default);
+    }
+
+    #[test]
+    fn test_timer_slack_proc() {
+        let tid = Pid::from_raw(unsafe { syscall!(Sysno::gettid) }.unwrap() as i32);
+        let slack = get_timer_slack(tid).unwrap();
+        assert_eq!(slack, get_timer_slack(Pid::this()).unwrap());
+        if set_timer_slack(tid, 1_000).is_ok() {
+            assert_eq!(get_timer_slack(Pid::this()).unwrap(), 1_000);
+            set_timer_slack(tid, slack).unwrap();
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use syscalls::Errno;
+
+use crate::{
+    sched::{set_affinity, set_attr, set_timer_slack, Attributes, Pid},
+    CpuSet,
+};
+
+/// Scheduling setup of a real-time thread applied in one step.
+///
+/// ```no_run
+/// use linux_rt::sched::{Attributes, Pid, Policy, SchedFlags, ThreadConfig};
+/// use linux_rt::CpuSet;
+///
+/// let attr = Attributes {
+///     policy: Policy::Fifo,
+///     flags: SchedFlags::SCHED_FLAG_RESET_ON_FORK,
+///     nice: 0,
+///     priority: 80,
+///     runtime_ns: 0,
+///     deadline_ns: 0,
+///     period_ns: 0,
+///     sched_util_min: 0,
+///     sched_util_max: 0,
+/// };
+/// ThreadConfig::new()
+///     .with_timer_slack(1)
+///     .with_affinity(CpuSet::from_slice([3]))
+///     .with_attributes(attr)
+///     .apply(Pid::this())
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ThreadConfig {
+    timer_slack_ns: Option<u64>,
+    affinity: Option<CpuSet>,
+    attributes: Option<Attributes>,
+}
+
+impl ThreadConfig {
+    /// Creates a configuration that leaves the thread unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the timer slack in nanoseconds, see [set_timer_slack].
+    /// The minimum of 1 ns makes sleeps as precise as the hardware allows.
+    pub fn with_timer_slack(mut self, slack_ns: u64) -> Self {
+        self.timer_slack_ns = Some(slack_ns);
+        self
+    }
+
+    /// Sets the CPU affinity, see [set_affinity].
+    pub fn with_affinity(mut self, set: CpuSet) -> Self {
+        self.affinity = Some(set);
+        self
+    }
+
+    /// Sets the scheduling policy and attributes, see [set_attr].
+    pub fn with_attributes(mut self, attr: Attributes) -> Self {
+        self.attributes = Some(attr);
+        self
+    }
+
+    /// Applies the configuration to the thread `pid`.
+    ///
+    /// The timer slack is set first, because it can no longer be changed once
+    /// the thread runs with a real-time policy.
+    pub fn apply(&self, pid: Pid) -> Result<(), Errno> {
+        if let Some(slack_ns) = self.timer_slack_ns {
+            set_timer_slack(pid, slack_ns)?;
+        }
+        if let Some(set) = &self.affinity {
+            set_affinity(pid, set.clone())?;
+        }
+        if let Some(attr) = &self.attributes {
+            set_attr(pid, attr.clone())?;
+        }
+        Ok(())
+    }
+}
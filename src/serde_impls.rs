@@ -0,0 +1,178 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{CpuSet, TimeSpec};
+
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+    s.parse().map_err(de::Error::custom)
+}
+
+/// Serialized as a duration string, e.g. `"1.5ms"`. Values that do not fit
+/// into an `i64` of nanoseconds are rejected, as they could not be
+/// deserialized again.
+impl Serialize for TimeSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        i64::try_from(self.as_nanoseconds_i128()).map_err(serde::ser::Error::custom)?;
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_from_str(deserializer)
+    }
+}
+
+/// Serialized as a cpulist string, e.g. `"0-3,8"`.
+impl Serialize for CpuSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CpuSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_from_str(deserializer)
+    }
+}
+
+/// Serializes a nanosecond count as a duration string, e.g. `"500us"`.
+pub(crate) mod nanoseconds {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(nanoseconds: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        let nanoseconds = i64::try_from(*nanoseconds).map_err(serde::ser::Error::custom)?;
+        TimeSpec::nanoseconds(nanoseconds).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let ts = TimeSpec::deserialize(deserializer)?;
+        u64::try_from(ts.as_nanoseconds()).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clock::{ClockId, StatusCodes, Timex, TimexMode},
+        lowlevel::clock::Timeval,
+        mman::MmanFlags,
+        sched::{Attributes, Policy, SchedFlags},
+        CpuSet, TimeSpec,
+    };
+
+    fn round_trip<T>(value: &T) -> String
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+    {
+        let json = serde_json::to_string(value).unwrap();
+        assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value);
+        json
+    }
+
+    #[test]
+    fn test_time() {
+        assert_eq!(round_trip(&TimeSpec::nanoseconds(1_500_000)), r#""1.5ms""#);
+        assert_eq!(
+            round_trip(&Timeval {
+                tv_sec: 1,
+                tv_usec: 2
+            }),
+            r#"{"tv_sec":1,"tv_usec":2}"#
+        );
+        assert_eq!(round_trip(&ClockId::ClockMonotonic), r#""monotonic""#);
+        assert_eq!(round_trip(&ClockId::Dynamic(-29)), r#"{"dynamic":-29}"#);
+        assert!(serde_json::from_str::<TimeSpec>(r#""1.5""#).is_err());
+        round_trip(&TimeSpec::nanoseconds(i64::MAX));
+        round_trip(&TimeSpec::nanoseconds(i64::MIN));
+        assert!(serde_json::to_string(&TimeSpec::seconds(i64::MAX)).is_err());
+        assert!(serde_json::to_string(&TimeSpec::seconds(i64::MIN)).is_err());
+    }
+
+    #[test]
+    fn test_sched() {
+        assert_eq!(round_trip(&Policy::RoundRobin), r#""round_robin""#);
+        assert_eq!(round_trip(&CpuSet::from_slice([0, 1, 2, 5])), r#""0-2,5""#);
+        round_trip(&(SchedFlags::SCHED_FLAG_RESET_ON_FORK | SchedFlags::SCHED_FLAG_RECLAIM));
+
+        let attr = Attributes {
+            policy: Policy::Deadline,
+            flags: SchedFlags::SCHED_FLAG_DL_OVERRUN,
+            nice: 0,
+            priority: 0,
+            runtime_ns: 500_000,
+            deadline_ns: 1_000_000,
+            period_ns: 2_000_000_000,
+            sched_util_min: 0,
+            sched_util_max: 1024,
+        };
+        let json = round_trip(&attr);
+        assert!(json.contains(r#""policy":"deadline""#));
+        assert!(json.contains(r#""runtime_ns":"500us""#));
+        assert!(json.contains(r#""deadline_ns":"1ms""#));
+        assert!(json.contains(r#""period_ns":"2s""#));
+        assert!(json.contains(r#""flags":"SCHED_FLAG_DL_OVERRUN""#));
+    }
+
+    #[test]
+    fn test_mman() {
+        assert_eq!(
+            round_trip(&(MmanFlags::MCL_CURRENT | MmanFlags::MCL_FUTURE)),
+            r#""MCL_CURRENT | MCL_FUTURE""#
+        );
+    }
+
+    #[test]
+    fn test_timex() {
+        let timex = Timex {
+            modes: TimexMode::ADJ_STATUS | TimexMode::ADJ_NANO,
+            status: StatusCodes::STA_PLL | StatusCodes::STA_INS,
+            tai: 37,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&timex).unwrap();
+        assert!(json.contains(r#""modes":"ADJ_STATUS | ADJ_NANO""#));
+        let back: Timex = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.modes, timex.modes);
+        assert_eq!(back.status, timex.status);
+        assert_eq!(back.tai, 37);
+    }
+
+    #[test]
+    fn test_timex_round_trip() {
+        let timex = Timex {
+            modes: TimexMode::ADJ_OFFSET | TimexMode::ADJ_FREQUENCY,
+            offset: -1,
+            freq: 2,
+            maxerror: 3,
+            esterror: 4,
+            status: StatusCodes::STA_NANO | StatusCodes::STA_UNSYNC,
+            constant: 5,
+            precision: 6,
+            tolerance: 7,
+            time: Timeval {
+                tv_sec: 8,
+                tv_usec: 9,
+            },
+            tick: 10,
+            ppsfreq: 11,
+            jitter: 12,
+            shift: 13,
+            stabil: 14,
+            jitcnt: 15,
+            calcnt: 16,
+            errcnt: 17,
+            stbcnt: 18,
+            tai: 19,
+        };
+        let json = serde_json::to_string(&timex).unwrap();
+        let back: Timex = serde_json::from_str(&json).unwrap();
+        // Timex has no PartialEq, its Debug output covers every field.
+        assert_eq!(format!("{back:?}"), format!("{timex:?}"));
+    }
+}
@@ -0,0 +1,49 @@
+use std::{cell::Cell, sync::Once};
+
+use crate::lowlevel::futex::gettid;
+
+mod condvar;
+mod mutex;
+
+pub use condvar::PiCondvar;
+pub use mutex::{PiMutex, PiMutexGuard};
+
+thread_local! {
+    static TID: Cell<u32> = const { Cell::new(0) };
+}
+
+extern "C" fn reset_tid() {
+    TID.set(0);
+}
+
+/// Returns the thread ID of the calling thread, which is the value of a
+/// locked PI futex. It is cached, so only the first call of a thread makes
+/// a system call. A `pthread_atfork` handler clears the cache in the child,
+/// whose only thread has a new ID.
+fn current_tid() -> u32 {
+    static ATFORK: Once = Once::new();
+    match TID.get() {
+        0 => {
+            ATFORK.call_once(|| unsafe {
+                libc::pthread_atfork(None, None, Some(reset_tid));
+            });
+            let tid = unsafe { gettid() }.expect("gettid cannot fail") as u32;
+            TID.set(tid);
+            tid
+        }
+        tid => tid,
+    }
+}
+
+/// Returns the effective priority of the thread `tid` in the kernel's
+/// notation, where lower values are more important and real-time
+/// priorities map to `99 - priority`.
+#[cfg(test)]
+fn effective_priority(tid: crate::sched::Pid) -> i32 {
+    let sched = std::fs::read_to_string(format!("/proc/self/task/{}/sched", tid.as_raw())).unwrap();
+    sched
+        .lines()
+        .find_map(|line| line.strip_prefix("prio")?.trim().strip_prefix(':'))
+        .and_then(|prio| prio.trim().parse().ok())
+        .unwrap()
+}
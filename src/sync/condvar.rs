@@ -0,0 +1,509 @@
+use std::{
+    ffi::c_int,
+    mem, ptr,
+    sync::atomic::{AtomicIsize, AtomicU32, Ordering},
+};
+
+use syscalls::Errno;
+
+use crate::{
+    clock::ClockId,
+    lowlevel::futex::{
+        futex, FUTEX_CLOCK_REALTIME, FUTEX_CMP_REQUEUE_PI, FUTEX_PRIVATE_FLAG,
+        FUTEX_WAIT_REQUEUE_PI,
+    },
+    sync::{PiMutex, PiMutexGuard},
+    TimeSpec,
+};
+
+/// A condition variable for use with a [PiMutex] that preserves priority
+/// inheritance on wakeup, built on requeue-PI futexes.
+///
+/// Notified waiters are not woken to contend for the mutex. The kernel
+/// moves them from the condition variable to the PI futex of the mutex
+/// instead, where they boost its owner and acquire it in priority order,
+/// as if they had blocked in [PiMutex::lock].
+///
+/// A condition variable is bound to the mutex of its first wait, waiting
+/// with another mutex fails with `EINVAL`. Like the mutex, it can be
+/// placed in shared memory if created with [PiCondvar::new_shared]. The
+/// mutex then has to be in the same mapping.
+#[derive(Debug)]
+#[repr(C)]
+pub struct PiCondvar {
+    /// Incremented by every notification.
+    seq: AtomicU32,
+    waiters: AtomicU32,
+    private: bool,
+    /// Position of the futex word of the bound mutex relative to the
+    /// condition variable, which is the same in every process mapping it.
+    /// 0 before the first wait.
+    mutex: AtomicIsize,
+}
+
+impl PiCondvar {
+    /// Creates a condition variable for a mutex created with [PiMutex::new].
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+            waiters: AtomicU32::new(0),
+            private: true,
+            mutex: AtomicIsize::new(0),
+        }
+    }
+
+    /// Creates a condition variable for a mutex created with
+    /// [PiMutex::new_shared].
+    pub const fn new_shared() -> Self {
+        Self {
+            private: false,
+            ..Self::new()
+        }
+    }
+
+    fn op(&self, op: c_int) -> c_int {
+        if self.private {
+            op | FUTEX_PRIVATE_FLAG
+        } else {
+            op
+        }
+    }
+
+    fn bind<T: ?Sized>(&self, mutex: &PiMutex<T>) -> Result<(), Errno> {
+        if mutex.private != self.private {
+            return Err(Errno::EINVAL);
+        }
+        let offset = (mutex.futex_ptr() as isize).wrapping_sub(self.seq.as_ptr() as isize);
+        match self
+            .mutex
+            .compare_exchange(0, offset, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => Ok(()),
+            Err(bound) if bound == offset => Ok(()),
+            Err(_) => Err(Errno::EINVAL),
+        }
+    }
+
+    fn wait_inner<'a, T: ?Sized>(
+        &self,
+        guard: PiMutexGuard<'a, T>,
+        op: c_int,
+        timeout: *const TimeSpec,
+    ) -> Result<(PiMutexGuard<'a, T>, bool), Errno> {
+        let (mutex, tid) = (guard.mutex, guard.tid);
+        self.bind(mutex)?;
+        // Counting the waiter before reading the sequence ensures that a
+        // notifier either sees the waiter or the waiter sees the new
+        // sequence and does not block.
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let seq = self.seq.load(Ordering::SeqCst);
+        mem::forget(guard);
+        mutex.unlock(tid);
+        let ret = unsafe {
+            futex(
+                self.seq.as_ptr(),
+                self.op(op),
+                seq,
+                timeout,
+                mutex.futex_ptr(),
+                0,
+            )
+        };
+        self.waiters.fetch_sub(1, Ordering::Relaxed);
+        let timed_out = match ret {
+            // The kernel acquired the mutex on behalf of the waiter.
+            Ok(_) => return Ok((PiMutexGuard::new(mutex, tid), false)),
+            Err(Errno::ETIMEDOUT) => true,
+            // A notification came in before the wait or a signal
+            // interrupted it.
+            Err(Errno::EAGAIN) | Err(Errno::EINTR) => false,
+            Err(err) => return Err(err),
+        };
+        Ok((mutex.lock()?, timed_out))
+    }
+
+    /// Unlocks the mutex of `guard`, blocks until notified and locks the
+    /// mutex again. Spurious wakeups are possible, so the condition has to
+    /// be checked in a loop.
+    ///
+    /// On errors the mutex is left unlocked.
+    pub fn wait<'a, T: ?Sized>(
+        &self,
+        guard: PiMutexGuard<'a, T>,
+    ) -> Result<PiMutexGuard<'a, T>, Errno> {
+        let (guard, _) = self.wait_inner(guard, FUTEX_WAIT_REQUEUE_PI, ptr::null())?;
+        Ok(guard)
+    }
+
+    /// Like [PiCondvar::wait], but stops waiting when the absolute
+    /// `deadline` on the clock `clockid` has passed. Returns `true` with
+    /// the guard if the deadline passed.
+    ///
+    /// Only `ClockMonotonic` and `ClockRealtime` are supported, other
+    /// clocks fail with `EINVAL`.
+    pub fn wait_until<'a, T: ?Sized>(
+        &self,
+        guard: PiMutexGuard<'a, T>,
+        deadline: TimeSpec,
+        clockid: ClockId,
+    ) -> Result<(PiMutexGuard<'a, T>, bool), Errno> {
+        let op = match clockid {
+            ClockId::ClockMonotonic => FUTEX_WAIT_REQUEUE_PI,
+            ClockId::ClockRealtime => FUTEX_WAIT_REQUEUE_PI | FUTEX_CLOCK_REALTIME,
+            _ => return Err(Errno::EINVAL),
+        };
+        self.wait_inner(guard, op, &deadline)
+    }
+
+    fn notify(&self, requeue: u32) -> Result<(), Errno> {
+        let mut seq = self.seq.fetch_add(1, Ordering::SeqCst).wrapping_add(1);
+        if self.waiters.load(Ordering::SeqCst) == 0 {
+            return Ok(());
+        }
+        let mutex = self
+            .seq
+            .as_ptr()
+            .cast_const()
+            .wrapping_byte_offset(self.mutex.load(Ordering::Relaxed));
+        loop {
+            // The kernel takes the number of waiters to requeue in place of
+            // the timeout. Exactly one waiter may be woken, which happens
+            // only if the mutex is unlocked; otherwise it is requeued too.
+            let ret = unsafe {
+                futex(
+                    self.seq.as_ptr(),
+                    self.op(FUTEX_CMP_REQUEUE_PI),
+                    1,
+                    requeue as usize as *const TimeSpec,
+                    mutex,
+                    seq,
+                )
+            };
+            match ret {
+                Ok(_) => return Ok(()),
+                // Another notification changed the sequence.
+                Err(Errno::EAGAIN) => seq = self.seq.load(Ordering::SeqCst),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Wakes up one waiter, the most important one. It acquires the mutex
+    /// when the notifying thread unlocks it.
+    pub fn notify_one(&self) -> Result<(), Errno> {
+        self.notify(0)
+    }
+
+    /// Wakes up all waiters. They acquire the mutex one after another in
+    /// priority order.
+    pub fn notify_all(&self) -> Result<(), Errno> {
+        self.notify(i32::MAX as u32)
+    }
+}
+
+impl Default for PiCondvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::VecDeque,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{
+        clock::get_time,
+        ipc::SharedRegion,
+        sched::{set_fifo, Pid},
+        sync::{current_tid, effective_priority},
+    };
+
+    #[test]
+    fn test_wait_until() {
+        let mutex = PiMutex::new(());
+        let condvar = PiCondvar::new();
+        let mut guard = mutex.lock().unwrap();
+        for clockid in [ClockId::ClockMonotonic, ClockId::ClockRealtime] {
+            let deadline = get_time(clockid).unwrap() + TimeSpec::nanoseconds(10_000_000);
+            let (next, timed_out) = condvar.wait_until(guard, deadline, clockid).unwrap();
+            guard = next;
+            assert!(timed_out);
+            assert!(get_time(clockid).unwrap() >= deadline);
+            assert!(mutex.owner().is_some());
+        }
+        let deadline = get_time(ClockId::ClockBoottime).unwrap();
+        assert_eq!(
+            condvar
+                .wait_until(guard, deadline, ClockId::ClockBoottime)
+                .unwrap_err(),
+            Errno::EINVAL
+        );
+        assert_eq!(mutex.owner(), None);
+
+        let other = PiMutex::new(());
+        assert_eq!(
+            condvar.wait(other.lock().unwrap()).unwrap_err(),
+            Errno::EINVAL
+        );
+        let shared = PiMutex::new_shared(());
+        assert_eq!(
+            PiCondvar::new().wait(shared.lock().unwrap()).unwrap_err(),
+            Errno::EINVAL
+        );
+        condvar.notify_all().unwrap();
+    }
+
+    #[test]
+    fn test_handoff() {
+        const COUNT: u64 = 10_000;
+        let queue = Arc::new((
+            PiMutex::new(VecDeque::new()),
+            PiCondvar::new(),
+            PiCondvar::new(),
+        ));
+        let sum = Arc::new(AtomicU64::new(0));
+        let mut threads = Vec::new();
+        for _ in 0..2 {
+            let queue = queue.clone();
+            threads.push(thread::spawn(move || {
+                let (mutex, not_empty, not_full) = &*queue;
+                for i in 1..=COUNT {
+                    let mut guard = mutex.lock().unwrap();
+                    while guard.len() >= 4 {
+                        guard = not_full.wait(guard).unwrap();
+                    }
+                    guard.push_back(i);
+                    not_empty.notify_one().unwrap();
+                }
+            }));
+        }
+        for _ in 0..2 {
+            let (queue, sum) = (queue.clone(), sum.clone());
+            threads.push(thread::spawn(move || {
+                let (mutex, not_empty, not_full) = &*queue;
+                for _ in 0..COUNT {
+                    let mut guard = mutex.lock().unwrap();
+                    let value = loop {
+                        match guard.pop_front() {
+                            Some(value) => break value,
+                            None => guard = not_empty.wait(guard).unwrap(),
+                        }
+                    };
+                    not_full.notify_one().unwrap();
+                    sum.fetch_add(value, Ordering::Relaxed);
+                }
+            }));
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(sum.load(Ordering::Relaxed), COUNT * (COUNT + 1));
+    }
+
+    #[test]
+    fn test_requeue_boost() {
+        struct State {
+            waiting: usize,
+            go: bool,
+            order: Vec<u32>,
+        }
+        let shared = Arc::new((
+            PiMutex::new(State {
+                waiting: 0,
+                go: false,
+                order: Vec::new(),
+            }),
+            PiCondvar::new(),
+        ));
+        let priorities = [10, 30, 20];
+        let threads: Vec<_> = priorities
+            .iter()
+            .map(|&priority| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    let (mutex, condvar) = &*shared;
+                    let fifo = set_fifo(Pid::this(), priority);
+                    let mut guard = mutex.lock().unwrap();
+                    guard.waiting += 1;
+                    while !guard.go {
+                        guard = condvar.wait(guard).unwrap();
+                    }
+                    guard.order.push(priority);
+                    fifo
+                })
+            })
+            .collect();
+
+        let (mutex, condvar) = &*shared;
+        let mut guard = mutex.lock().unwrap();
+        while guard.waiting < priorities.len() {
+            drop(guard);
+            thread::sleep(Duration::from_millis(1));
+            guard = mutex.lock().unwrap();
+        }
+        let owner = mutex.owner().unwrap();
+        let normal = effective_priority(owner);
+        guard.go = true;
+        condvar.notify_all().unwrap();
+        // The requeued waiters boost the owner to the highest priority.
+        let mut boosted = false;
+        for _ in 0..1000 {
+            if effective_priority(owner) == 99 - 30 {
+                boosted = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        drop(guard);
+
+        let results: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(effective_priority(owner), normal);
+        if results.iter().all(Result::is_ok) {
+            assert!(boosted);
+            assert_eq!(mutex.lock().unwrap().order, [30, 20, 10]);
+        } else {
+            assert!(results.contains(&Err(Errno::EPERM)));
+            assert_eq!(mutex.lock().unwrap().order.len(), priorities.len());
+            eprintln!("test_requeue_boost skipped: SCHED_FIFO requires CAP_SYS_NICE");
+        }
+    }
+
+    /// Returns `true` if the thread `tid` of the calling process is blocked.
+    fn sleeping(tid: u32) -> bool {
+        let stat = std::fs::read_to_string(format!("/proc/self/task/{tid}/stat")).unwrap();
+        // The state follows the parenthesized command name.
+        stat.rsplit_once(") ")
+            .is_some_and(|(_, rest)| rest.starts_with('S'))
+    }
+
+    #[test]
+    fn test_boost_stress() {
+        const ROUNDS: usize = 200;
+        const PRIORITIES: [u32; 4] = [10, 40, 20, 30];
+        if thread::spawn(|| set_fifo(Pid::this(), 1)).join().unwrap() == Err(Errno::EPERM) {
+            eprintln!("test_boost_stress skipped: SCHED_FIFO requires CAP_SYS_NICE");
+            return;
+        }
+        struct State {
+            round: usize,
+            /// Threads waiting in the current round.
+            waiting: Vec<u32>,
+            /// Priorities of the threads woken in the previous round, in
+            /// the order they acquired the mutex.
+            woken: Vec<u32>,
+        }
+        let shared = Arc::new((
+            PiMutex::new(State {
+                round: 0,
+                waiting: Vec::new(),
+                woken: Vec::new(),
+            }),
+            PiCondvar::new(),
+        ));
+        let threads: Vec<_> = PRIORITIES
+            .iter()
+            .map(|&priority| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    let (mutex, condvar) = &*shared;
+                    set_fifo(Pid::this(), priority).unwrap();
+                    for round in 0..ROUNDS {
+                        let mut guard = mutex.lock().unwrap();
+                        guard.waiting.push(current_tid());
+                        while guard.round == round {
+                            guard = condvar.wait(guard).unwrap();
+                        }
+                        guard.woken.push(priority);
+                    }
+                })
+            })
+            .collect();
+
+        let (mutex, condvar) = &*shared;
+        let owner = Pid::from_raw(current_tid() as i32);
+        let normal = effective_priority(owner);
+        let mut expected = PRIORITIES;
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        for round in 0..ROUNDS {
+            // Notify only once all threads block on the condition variable,
+            // so that all of them are requeued.
+            let mut guard = loop {
+                let guard = mutex.lock().unwrap();
+                if guard.waiting.len() == PRIORITIES.len()
+                    && guard.waiting.iter().all(|&tid| sleeping(tid))
+                {
+                    break guard;
+                }
+                drop(guard);
+                thread::sleep(Duration::from_micros(100));
+            };
+            if round > 0 {
+                assert_eq!(guard.woken, expected);
+            }
+            guard.round += 1;
+            guard.waiting.clear();
+            guard.woken.clear();
+            if round % 2 == 0 {
+                condvar.notify_all().unwrap();
+            } else {
+                for _ in PRIORITIES {
+                    condvar.notify_one().unwrap();
+                }
+            }
+            // The requeued waiters boost the owner to the highest priority.
+            assert_eq!(effective_priority(owner), 99 - expected[0] as i32);
+            drop(guard);
+            assert_eq!(effective_priority(owner), normal);
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(mutex.lock().unwrap().woken, expected);
+    }
+
+    #[test]
+    fn test_shared() {
+        #[repr(C)]
+        struct Shared {
+            mutex: PiMutex<bool>,
+            condvar: PiCondvar,
+        }
+        let region = SharedRegion::new("test", size_of::<Shared>()).unwrap();
+        let shared = region.as_ptr().cast::<Shared>();
+        unsafe {
+            shared.write(Shared {
+                mutex: PiMutex::new_shared(false),
+                condvar: PiCondvar::new_shared(),
+            })
+        };
+        let shared = unsafe { &*shared };
+        match unsafe { nix::unistd::fork() }.unwrap() {
+            nix::unistd::ForkResult::Child => {
+                let mut guard = shared.mutex.lock().unwrap();
+                while !*guard {
+                    guard = shared.condvar.wait(guard).unwrap();
+                }
+                *guard = false;
+                drop(guard);
+                unsafe { libc::_exit(0) };
+            }
+            nix::unistd::ForkResult::Parent { child } => {
+                thread::sleep(Duration::from_millis(10));
+                *shared.mutex.lock().unwrap() = true;
+                shared.condvar.notify_one().unwrap();
+                let status = nix::sys::wait::waitpid(child, None).unwrap();
+                assert_eq!(status, nix::sys::wait::WaitStatus::Exited(child, 0));
+                assert!(!*shared.mutex.lock().unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,462 @@
+use std::{
+    cell::UnsafeCell,
+    ffi::c_int,
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use syscalls::Errno;
+
+use crate::{
+    clock::ClockId,
+    lowlevel::futex::{
+        futex, FUTEX_LOCK_PI, FUTEX_LOCK_PI2, FUTEX_PRIVATE_FLAG, FUTEX_TID_MASK, FUTEX_TRYLOCK_PI,
+        FUTEX_UNLOCK_PI,
+    },
+    sched::Pid,
+    sync::current_tid,
+    TimeSpec,
+};
+
+/// A mutual exclusion lock with priority inheritance, built on PI futexes.
+///
+/// While a thread blocks on the mutex, the kernel boosts the owner to the
+/// priority of the most important waiter, which bounds priority inversion
+/// between a `SCHED_FIFO` thread and lower-priority threads sharing data.
+/// Uncontended locking and unlocking does not enter the kernel.
+///
+/// Unlike [std::sync::Mutex] the lock is not poisoned if a thread panics
+/// while holding it. Locking fails with `EDEADLK` if the calling thread
+/// already owns the mutex.
+///
+/// A mutex created with [PiMutex::new_shared] can be placed in shared
+/// memory, e.g. a [crate::ipc::SharedRegion], and locked from several
+/// processes.
+///
+/// The mutex is unlocked when the guard is dropped, which cannot report
+/// errors. `FUTEX_UNLOCK_PI` only fails if the futex word was corrupted,
+/// e.g. by a peer writing to the shared memory. The mutex then stays locked,
+/// and only debug builds panic.
+///
+/// ```no_run
+/// use linux_rt::{ipc::SharedRegion, sync::PiMutex};
+///
+/// let region = SharedRegion::new("counter", size_of::<PiMutex<u64>>()).unwrap();
+/// let mutex = region.as_ptr().cast::<PiMutex<u64>>();
+/// unsafe { mutex.write(PiMutex::new_shared(0)) };
+/// let mutex = unsafe { &*mutex };
+/// *mutex.lock().unwrap() += 1;
+/// ```
+#[repr(C)]
+pub struct PiMutex<T: ?Sized> {
+    /// 0 if unlocked, otherwise the TID of the owner and `FUTEX_WAITERS`
+    /// if threads are blocked in the kernel.
+    futex: AtomicU32,
+    pub(super) private: bool,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for PiMutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for PiMutex<T> {}
+
+impl<T> PiMutex<T> {
+    /// Creates an unlocked mutex that is only used within the calling
+    /// process, which lets the kernel skip the lookup of shared mappings.
+    pub const fn new(value: T) -> Self {
+        Self {
+            futex: AtomicU32::new(0),
+            private: true,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Creates an unlocked mutex that can be used by several processes, if
+    /// placed in memory mapped with `MAP_SHARED`.
+    ///
+    /// The mutex is not robust: if its owner terminates while holding it,
+    /// the mutex stays locked and every attempt to lock it fails with
+    /// `ESRCH`. The remaining processes have to agree on reinitializing it.
+    pub const fn new_shared(value: T) -> Self {
+        Self {
+            futex: AtomicU32::new(0),
+            private: false,
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Consumes the mutex and returns the protected value.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> PiMutex<T> {
+    pub(super) fn op(&self, op: c_int) -> c_int {
+        if self.private {
+            op | FUTEX_PRIVATE_FLAG
+        } else {
+            op
+        }
+    }
+
+    pub(super) fn futex_ptr(&self) -> *const u32 {
+        self.futex.as_ptr()
+    }
+
+    fn try_acquire(&self, tid: u32) -> bool {
+        self.futex
+            .compare_exchange(0, tid, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn lock_slow(&self, op: c_int, timeout: *const TimeSpec) -> Result<(), Errno> {
+        loop {
+            match unsafe { futex(self.futex_ptr(), self.op(op), 0, timeout, ptr::null(), 0) } {
+                Ok(_) => return Ok(()),
+                Err(Errno::EINTR) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Acquires the mutex, blocking the calling thread until it is
+    /// available.
+    pub fn lock(&self) -> Result<PiMutexGuard<'_, T>, Errno> {
+        let tid = current_tid();
+        if !self.try_acquire(tid) {
+            self.lock_slow(FUTEX_LOCK_PI, ptr::null())?;
+        }
+        Ok(PiMutexGuard::new(self, tid))
+    }
+
+    /// Acquires the mutex, blocking the calling thread until it is
+    /// available or the absolute `deadline` on the clock `clockid` has
+    /// passed, which fails with `ETIMEDOUT`.
+    ///
+    /// Only `ClockMonotonic` and `ClockRealtime` are supported, other
+    /// clocks fail with `EINVAL`. `ClockMonotonic` uses `FUTEX_LOCK_PI2`
+    /// and fails with `ENOSYS` before Linux 5.14.
+    pub fn lock_until(
+        &self,
+        deadline: TimeSpec,
+        clockid: ClockId,
+    ) -> Result<PiMutexGuard<'_, T>, Errno> {
+        let op = match clockid {
+            ClockId::ClockMonotonic => FUTEX_LOCK_PI2,
+            ClockId::ClockRealtime => FUTEX_LOCK_PI,
+            _ => return Err(Errno::EINVAL),
+        };
+        let tid = current_tid();
+        if !self.try_acquire(tid) {
+            self.lock_slow(op, &deadline)?;
+        }
+        Ok(PiMutexGuard::new(self, tid))
+    }
+
+    /// Acquires the mutex if it is available without blocking, and fails
+    /// with `EBUSY` otherwise, or `ESRCH` if the owner has terminated.
+    pub fn try_lock(&self) -> Result<PiMutexGuard<'_, T>, Errno> {
+        let tid = current_tid();
+        if self.try_acquire(tid) {
+            return Ok(PiMutexGuard::new(self, tid));
+        }
+        // The kernel tells apart an owner that is the calling thread
+        // (EDEADLK), another thread (EAGAIN) or has terminated (ESRCH).
+        let ret = unsafe {
+            futex(
+                self.futex_ptr(),
+                self.op(FUTEX_TRYLOCK_PI),
+                0,
+                ptr::null(),
+                ptr::null(),
+                0,
+            )
+        };
+        match ret {
+            Ok(_) => Ok(PiMutexGuard::new(self, tid)),
+            Err(Errno::EAGAIN) => Err(Errno::EBUSY),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the thread that currently owns the mutex.
+    pub fn owner(&self) -> Option<Pid> {
+        match self.futex.load(Ordering::Relaxed) & FUTEX_TID_MASK {
+            0 => None,
+            tid => Some(Pid::from_raw(tid as i32)),
+        }
+    }
+
+    /// Returns a mutable reference to the protected value. No locking is
+    /// needed, as the mutable borrow guarantees exclusive access.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// Releases the mutex, which must be owned by the calling thread `tid`.
+    pub(super) fn unlock(&self, tid: u32) {
+        if self
+            .futex
+            .compare_exchange(tid, 0, Ordering::Release, Ordering::Relaxed)
+            .is_err()
+        {
+            // There are waiters, the kernel hands the mutex over to the most
+            // important one.
+            let ret = unsafe {
+                futex(
+                    self.futex_ptr(),
+                    self.op(FUTEX_UNLOCK_PI),
+                    0,
+                    ptr::null(),
+                    ptr::null(),
+                    0,
+                )
+            };
+            // Fails only if the futex word is corrupted, see [PiMutex].
+            debug_assert!(ret.is_ok(), "FUTEX_UNLOCK_PI failed: {ret:?}");
+        }
+    }
+}
+
+impl<T: Default> Default for PiMutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for PiMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PiMutex")
+            .field("owner", &self.owner())
+            .field("private", &self.private)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Grants access to the value of a locked [PiMutex] and unlocks it on drop.
+///
+/// The guard cannot be sent to another thread, as a PI mutex must be
+/// unlocked by its owner.
+pub struct PiMutexGuard<'a, T: ?Sized> {
+    pub(super) mutex: &'a PiMutex<T>,
+    /// The owner, so that unlocking does not look it up again.
+    pub(super) tid: u32,
+    _thread: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for PiMutexGuard<'_, T> {}
+
+impl<'a, T: ?Sized> PiMutexGuard<'a, T> {
+    pub(super) fn new(mutex: &'a PiMutex<T>, tid: u32) -> Self {
+        Self {
+            mutex,
+            tid,
+            _thread: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for PiMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for PiMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for PiMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock(self.tid);
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for PiMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use super::*;
+    use crate::{
+        clock::get_time, ipc::SharedRegion, lowlevel::futex::gettid, sched::set_fifo,
+        sync::effective_priority,
+    };
+
+    #[test]
+    fn test_lock() {
+        let mutex = PiMutex::new(1);
+        {
+            let mut guard = mutex.lock().unwrap();
+            *guard += 1;
+            assert_eq!(mutex.owner(), Some(Pid::from_raw(current_tid() as i32)));
+            assert_eq!(mutex.try_lock().unwrap_err(), Errno::EDEADLK);
+            assert_eq!(mutex.lock().unwrap_err(), Errno::EDEADLK);
+        }
+        assert_eq!(mutex.owner(), None);
+        assert_eq!(*mutex.try_lock().unwrap(), 2);
+        assert_eq!(mutex.into_inner(), 2);
+    }
+
+    #[test]
+    fn test_contention() {
+        let mutex = Arc::new(PiMutex::new(0u64));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let mutex = mutex.clone();
+                thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        *mutex.lock().unwrap() += 1;
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*mutex.lock().unwrap(), 40_000);
+    }
+
+    #[test]
+    fn test_lock_until() {
+        let mutex = Arc::new(PiMutex::new(()));
+        let guard = mutex.lock().unwrap();
+        let other = mutex.clone();
+        thread::spawn(move || {
+            assert_eq!(other.try_lock().unwrap_err(), Errno::EBUSY);
+            for clockid in [ClockId::ClockMonotonic, ClockId::ClockRealtime] {
+                let deadline = get_time(clockid).unwrap() + TimeSpec::nanoseconds(10_000_000);
+                match other.lock_until(deadline, clockid) {
+                    Err(Errno::ETIMEDOUT) => {}
+                    Err(Errno::ENOSYS) if clockid == ClockId::ClockMonotonic => {}
+                    ret => panic!("{ret:?}"),
+                }
+                assert!(get_time(clockid).unwrap() >= deadline);
+            }
+            let deadline = get_time(ClockId::ClockBoottime).unwrap();
+            assert_eq!(
+                other
+                    .lock_until(deadline, ClockId::ClockBoottime)
+                    .unwrap_err(),
+                Errno::EINVAL
+            );
+        })
+        .join()
+        .unwrap();
+        drop(guard);
+
+        let deadline = get_time(ClockId::ClockRealtime).unwrap();
+        drop(mutex.lock_until(deadline, ClockId::ClockRealtime).unwrap());
+    }
+
+    #[test]
+    fn test_shared() {
+        let region = SharedRegion::new("test", size_of::<PiMutex<u64>>()).unwrap();
+        let mutex = region.as_ptr().cast::<PiMutex<u64>>();
+        unsafe { mutex.write(PiMutex::new_shared(0)) };
+        let mutex = unsafe { &*mutex };
+        let increment = || {
+            for _ in 0..10_000 {
+                let mut guard = mutex.lock().unwrap();
+                *guard += 1;
+                if *guard % 100 == 0 {
+                    thread::yield_now();
+                }
+            }
+        };
+        // The cached TID of the parent must not be used by the child.
+        let parent = current_tid();
+        match unsafe { nix::unistd::fork() }.unwrap() {
+            nix::unistd::ForkResult::Child => {
+                let tid = current_tid();
+                if tid == parent || tid != unsafe { gettid() }.unwrap() as u32 {
+                    unsafe { libc::_exit(1) };
+                }
+                increment();
+                unsafe { libc::_exit(0) };
+            }
+            nix::unistd::ForkResult::Parent { child } => {
+                increment();
+                let status = nix::sys::wait::waitpid(child, None).unwrap();
+                assert_eq!(status, nix::sys::wait::WaitStatus::Exited(child, 0));
+                assert_eq!(*mutex.lock().unwrap(), 20_000);
+            }
+        }
+    }
+
+    #[test]
+    fn test_owner_died() {
+        let region = SharedRegion::new("test", size_of::<PiMutex<()>>()).unwrap();
+        let mutex = region.as_ptr().cast::<PiMutex<()>>();
+        unsafe { mutex.write(PiMutex::new_shared(())) };
+        let mutex = unsafe { &*mutex };
+        match unsafe { nix::unistd::fork() }.unwrap() {
+            nix::unistd::ForkResult::Child => {
+                // Exit while holding the mutex.
+                let code = match mutex.lock() {
+                    Ok(guard) => {
+                        std::mem::forget(guard);
+                        0
+                    }
+                    Err(_) => 1,
+                };
+                unsafe { libc::_exit(code) };
+            }
+            nix::unistd::ForkResult::Parent { child } => {
+                let status = nix::sys::wait::waitpid(child, None).unwrap();
+                assert_eq!(status, nix::sys::wait::WaitStatus::Exited(child, 0));
+                assert_eq!(mutex.owner(), Some(Pid::from_raw(child.as_raw())));
+                assert_eq!(mutex.try_lock().unwrap_err(), Errno::ESRCH);
+                assert_eq!(mutex.lock().unwrap_err(), Errno::ESRCH);
+            }
+        }
+    }
+
+    #[test]
+    fn test_priority_inheritance() {
+        let mutex = Arc::new(PiMutex::new(()));
+        let guard = mutex.lock().unwrap();
+        let owner = mutex.owner().unwrap();
+        let normal = effective_priority(owner);
+
+        let waiter = mutex.clone();
+        let thread = thread::spawn(move || {
+            set_fifo(Pid::this(), 10)?;
+            drop(waiter.lock().unwrap());
+            Ok(())
+        });
+        // The owner runs with the priority of the waiter while it blocks.
+        let mut boosted = false;
+        for _ in 0..1000 {
+            if thread.is_finished() {
+                break;
+            }
+            if effective_priority(owner) == 99 - 10 {
+                boosted = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        drop(guard);
+        match thread.join().unwrap() {
+            Ok(()) => assert!(boosted),
+            Err(Errno::EPERM) => {
+                eprintln!("test_priority_inheritance skipped: SCHED_FIFO requires CAP_SYS_NICE")
+            }
+            Err(err) => panic!("{err}"),
+        }
+        assert_eq!(effective_priority(owner), normal);
+    }
+}
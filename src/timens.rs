@@ -0,0 +1,239 @@
+use std::{
+    fs::{self, File},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+    path::Path,
+};
+
+use syscalls::Errno;
+
+use crate::{
+    clock::ClockId,
+    lowlevel::{
+        futex::gettid,
+        io_errno,
+        timens::{setns, unshare, CLONE_NEWTIME},
+    },
+    sched::Pid,
+    TimeSpec,
+};
+
+/// Returns the path of `timens_offsets` of the calling thread. Namespaces
+/// belong to threads, and the file is only listed in the directory of the
+/// main thread, but exists for all threads.
+fn timens_offsets() -> Result<String, Errno> {
+    let tid = unsafe { gettid() }?;
+    Ok(format!("/proc/{tid}/timens_offsets"))
+}
+
+/// Offsets of the clocks in a time namespace relative to the initial time namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Offsets {
+    /// Offset of `ClockMonotonic` and its variants.
+    pub monotonic: TimeSpec,
+    /// Offset of `ClockBoottime` and `ClockBoottimeAlarm`.
+    pub boottime: TimeSpec,
+}
+
+impl Offsets {
+    /// Returns the offset for the clock [ClockId], or `None` if the clock
+    /// is not affected by time namespaces.
+    pub fn get(&self, clockid: ClockId) -> Option<TimeSpec> {
+        match clockid {
+            ClockId::ClockMonotonic
+            | ClockId::ClockMonotonicCoarse
+            | ClockId::ClockMonotonicRaw => Some(self.monotonic),
+            ClockId::ClockBoottime | ClockId::ClockBoottimeAlarm => Some(self.boottime),
+            _ => None,
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self, Errno> {
+        let mut offsets = Offsets::default();
+        for line in content.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(clock), Some(sec), Some(nsec)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let offset = TimeSpec {
+                tv_sec: sec.parse().map_err(|_| Errno::EINVAL)?,
+                tv_nsec: nsec.parse().map_err(|_| Errno::EINVAL)?,
+            };
+            match clock {
+                "monotonic" => offsets.monotonic = offset,
+                "boottime" => offsets.boottime = offset,
+                _ => {}
+            }
+        }
+        Ok(offsets)
+    }
+}
+
+/// Creates a new time namespace for the children of the calling thread.
+///
+/// The calling thread stays in its time namespace. The offsets of the new
+/// namespace can be set with [set_offset] until the first process enters
+/// it, either by being created or through [TimeNamespace::enter].
+/// Requires `CAP_SYS_ADMIN`.
+pub fn unshare_time() -> Result<(), Errno> {
+    unsafe { unshare(CLONE_NEWTIME) }.and(Ok(()))
+}
+
+/// Sets the offset of `ClockMonotonic` or `ClockBoottime` in the time
+/// namespace created by [unshare_time]. Fails with `EINVAL` for other clocks
+/// and with `EACCES` once a process has entered the namespace.
+pub fn set_offset(clockid: ClockId, offset: TimeSpec) -> Result<(), Errno> {
+    let clock = match clockid {
+        ClockId::ClockMonotonic => "monotonic",
+        ClockId::ClockBoottime => "boottime",
+        _ => return Err(Errno::EINVAL),
+    };
+    let nanoseconds = offset.as_nanoseconds_i128();
+    let line = format!(
+        "{clock} {} {}\n",
+        nanoseconds.div_euclid(1_000_000_000),
+        nanoseconds.rem_euclid(1_000_000_000)
+    );
+    fs::write(timens_offsets()?, line).map_err(io_errno)
+}
+
+/// Returns the [Offsets] of the time namespace the children of the calling
+/// thread are placed in. This is the namespace of the thread itself, unless
+/// [unshare_time] was called.
+pub fn offsets() -> Result<Offsets, Errno> {
+    Offsets::parse(&fs::read_to_string(timens_offsets()?).map_err(io_errno)?)
+}
+
+/// A handle to a time namespace.
+#[derive(Debug)]
+pub struct TimeNamespace {
+    file: File,
+}
+
+impl TimeNamespace {
+    fn open(path: impl AsRef<Path>) -> Result<Self, Errno> {
+        let file = File::open(path).map_err(io_errno)?;
+        Ok(Self { file })
+    }
+
+    /// Returns the time namespace of the calling thread.
+    pub fn current() -> Result<Self, Errno> {
+        Self::open("/proc/thread-self/ns/time")
+    }
+
+    /// Returns the time namespace the children of the calling thread are
+    /// placed in, e.g. after [unshare_time].
+    pub fn for_children() -> Result<Self, Errno> {
+        Self::open("/proc/thread-self/ns/time_for_children")
+    }
+
+    /// Returns the time namespace of the process [Pid].
+    pub fn of(pid: Pid) -> Result<Self, Errno> {
+        Self::open(format!("/proc/{}/ns/time", pid.as_raw()))
+    }
+
+    /// Moves the calling process into the time namespace. This fails with
+    /// `EINVAL` if the process has more than one thread. Children created
+    /// afterwards are placed in the namespace as well.
+    pub fn enter(&self) -> Result<(), Errno> {
+        unsafe { setns(self.file.as_raw_fd(), CLONE_NEWTIME) }.and(Ok(()))
+    }
+}
+
+impl AsFd for TimeNamespace {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl AsRawFd for TimeNamespace {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let offsets =
+            Offsets::parse("monotonic           0         0\nboottime         -2000 500000000\n")
+                .unwrap();
+        assert_eq!(offsets.monotonic, TimeSpec::zeroed());
+        assert_eq!(
+            offsets.boottime,
+            TimeSpec {
+                tv_sec: -2000,
+                tv_nsec: 500_000_000
+            }
+        );
+        assert_eq!(
+            offsets.get(ClockId::ClockBoottimeAlarm),
+            Some(offsets.boottime)
+        );
+        assert_eq!(offsets.get(ClockId::ClockRealtime), None);
+    }
+
+    #[test]
+    fn test_offsets() {
+        offsets().unwrap();
+        TimeNamespace::current().unwrap();
+        assert_eq!(
+            set_offset(ClockId::ClockRealtime, TimeSpec::zeroed()),
+            Err(Errno::EINVAL)
+        );
+    }
+
+    /// Set in the environment of the process that runs [test_unshare] on
+    /// its own.
+    const UNSHARE_CHILD: &str = "LINUX_RT_TEST_UNSHARE";
+    const SKIPPED: &str = "skipped: unshare(CLONE_NEWTIME) requires CAP_SYS_ADMIN";
+
+    #[test]
+    fn test_unshare() {
+        // The namespace is created in a new process that runs only this
+        // test, so that the test process keeps its time namespace and nothing
+        // but exec runs between fork and exec.
+        if std::env::var_os(UNSHARE_CHILD).is_some() {
+            return unshare_child();
+        }
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "timens::tests::test_unshare", "--nocapture"])
+            .env(UNSHARE_CHILD, "1")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "{stdout}{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        if stdout.contains(SKIPPED) {
+            eprintln!("test_unshare {SKIPPED}");
+        }
+    }
+
+    fn unshare_child() {
+        match unshare_time() {
+            Err(Errno::EPERM) => return println!("{SKIPPED}"),
+            result => result.unwrap(),
+        }
+        let offset = TimeSpec::seconds(100_000);
+        set_offset(ClockId::ClockMonotonic, offset).unwrap();
+        TimeNamespace::for_children().unwrap();
+        assert_eq!(offsets().unwrap().monotonic, offset);
+        let output = Command::new("cat")
+            .arg("/proc/self/timens_offsets")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let offsets = Offsets::parse(&String::from_utf8(output.stdout).unwrap()).unwrap();
+        assert_eq!(offsets.monotonic, offset);
+        assert_eq!(offsets.boottime, TimeSpec::zeroed());
+    }
+}
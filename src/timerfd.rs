@@ -0,0 +1,380 @@
+use std::{
+    ffi::{c_int, c_void},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+};
+
+use bitflags::bitflags;
+use syscalls::Errno;
+
+use crate::{
+    clock::ClockId,
+    lowlevel::{
+        self,
+        capability::{has_effective, CAP_WAKE_ALARM},
+        timerfd::{
+            read, timerfd_create, timerfd_gettime, timerfd_settime, TFD_TIMER_ABSTIME,
+            TFD_TIMER_CANCEL_ON_SET,
+        },
+    },
+    TimeSpec,
+};
+
+pub use crate::lowlevel::timerfd::ItimerSpec;
+
+bitflags! {
+    /// These flags change the behavior of [TimerFd::new].
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct TimerFdFlags: c_int {
+        /// Set the O_NONBLOCK file status flag on the open file
+        /// description. [TimerFd::wait] then fails with `EAGAIN`
+        /// instead of blocking if the timer has not yet expired.
+        const TFD_NONBLOCK = lowlevel::timerfd::flags::TFD_NONBLOCK;
+        /// Set the close-on-exec (FD_CLOEXEC) flag on the new file
+        /// descriptor.
+        const TFD_CLOEXEC = lowlevel::timerfd::flags::TFD_CLOEXEC;
+    }
+}
+
+bitflags! {
+    /// These flags change how [TimerFd::set] interprets the new value.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct TimerSetFlags: c_int {
+        /// Interpret `it_value` as an absolute value on the timer's clock.
+        const TFD_TIMER_ABSTIME = TFD_TIMER_ABSTIME;
+        /// Together with `TFD_TIMER_ABSTIME` on a `ClockRealtime` or
+        /// `ClockRealtimeAlarm` timer: mark the timer as cancelable if the
+        /// real-time clock undergoes a discontinuous change. [TimerFd::wait]
+        /// then reports [Expiration::ClockSet].
+        const TFD_TIMER_CANCEL_ON_SET = TFD_TIMER_CANCEL_ON_SET;
+    }
+}
+
+/// Outcome of waiting on a [TimerFd].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiration {
+    /// The timer expired the given number of times since it was last
+    /// set or waited on.
+    Expired(u64),
+    /// The timer was set with `TFD_TIMER_CANCEL_ON_SET` and the
+    /// real-time clock underwent a discontinuous change (`ECANCELED`).
+    /// The timer has to be set again.
+    ClockSet,
+}
+
+/// A timer that delivers expiration notifications via a file descriptor.
+///
+/// The file descriptor can be monitored by `select`, `poll` and `epoll`
+/// and is closed on drop.
+#[derive(Debug)]
+pub struct TimerFd {
+    fd: OwnedFd,
+    clockid: ClockId,
+}
+
+impl TimerFd {
+    /// Creates a new timer on the specified clock [ClockId]. Supported
+    /// clocks are `ClockRealtime`, `ClockMonotonic`, `ClockBoottime`,
+    /// `ClockRealtimeAlarm` and `ClockBoottimeAlarm`. The alarm clocks
+    /// require the `CAP_WAKE_ALARM` capability.
+    pub fn new(clockid: ClockId, flags: TimerFdFlags) -> Result<Self, Errno> {
+        let fd = unsafe { timerfd_create(clockid.as_raw(), flags.bits()) }?;
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd as RawFd) },
+            clockid,
+        })
+    }
+
+    /// Returns the [ClockId] the timer was created on.
+    pub fn clock_id(&self) -> ClockId {
+        self.clockid
+    }
+
+    /// Arms or disarms the timer with `new_value` and returns the previous
+    /// setting. A zero `it_value` disarms the timer.
+    pub fn set(&self, new_value: ItimerSpec, flags: TimerSetFlags) -> Result<ItimerSpec, Errno> {
+        let mut old_value = ItimerSpec::default();
+        unsafe {
+            timerfd_settime(
+                self.fd.as_raw_fd(),
+                flags.bits(),
+                &new_value,
+                &raw mut old_value,
+            )
+        }
+        .and(Ok(old_value))
+    }
+
+    /// Arms the timer to expire after `value` and then every `interval`.
+    /// A zero `interval` makes it a one-shot timer.
+    pub fn set_relative(&self, value: TimeSpec, interval: TimeSpec) -> Result<(), Errno> {
+        let new_value = ItimerSpec {
+            it_interval: interval,
+            it_value: value,
+        };
+        self.set(new_value, TimerSetFlags::empty()).and(Ok(()))
+    }
+
+    /// Arms the timer to expire when the clock reaches `deadline` and then
+    /// every `interval`. If `cancel_on_set` is true, a discontinuous change
+    /// of the real-time clock is reported as [Expiration::ClockSet].
+    pub fn set_absolute(
+        &self,
+        deadline: TimeSpec,
+        interval: TimeSpec,
+        cancel_on_set: bool,
+    ) -> Result<(), Errno> {
+        let new_value = ItimerSpec {
+            it_interval: interval,
+            it_value: deadline,
+        };
+        let mut flags = TimerSetFlags::TFD_TIMER_ABSTIME;
+        flags.set(TimerSetFlags::TFD_TIMER_CANCEL_ON_SET, cancel_on_set);
+        self.set(new_value, flags).and(Ok(()))
+    }
+
+    /// Disarms the timer.
+    pub fn disarm(&self) -> Result<(), Errno> {
+        self.set(ItimerSpec::default(), TimerSetFlags::empty())
+            .and(Ok(()))
+    }
+
+    /// Returns the time until the next expiration and the interval of the timer.
+    /// The `it_value` is always relative, regardless of how the timer was set.
+    pub fn get(&self) -> Result<ItimerSpec, Errno> {
+        let mut curr_value = ItimerSpec::default();
+        unsafe { timerfd_gettime(self.fd.as_raw_fd(), &raw mut curr_value) }.and(Ok(curr_value))
+    }
+
+    /// Blocks until the timer expires and returns the number of expirations
+    /// since the last [TimerFd::wait]. With `TFD_NONBLOCK`, fails with
+    /// `EAGAIN` if the timer has not expired.
+    pub fn wait(&self) -> Result<Expiration, Errno> {
+        let mut expirations = 0u64;
+        let ret = unsafe {
+            read(
+                self.fd.as_raw_fd(),
+                &raw mut expirations as *mut c_void,
+                size_of::<u64>(),
+            )
+        };
+        match ret {
+            Ok(_) => Ok(Expiration::Expired(expirations)),
+            Err(Errno::ECANCELED) => Ok(Expiration::ClockSet),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl AsFd for TimerFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for TimerFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl From<TimerFd> for OwnedFd {
+    fn from(timer: TimerFd) -> Self {
+        timer.fd
+    }
+}
+
+/// What [AlarmTimer::new] does if the alarm clocks are not available, i.e.
+/// the calling thread lacks `CAP_WAKE_ALARM` or the system has no RTC that
+/// supports alarms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlarmFallback {
+    /// Fail with `EPERM`, `ENODEV` or `EOPNOTSUPP`.
+    #[default]
+    Fail,
+    /// Create the timer on the corresponding non-alarm clock. The timer
+    /// still counts the time spent in suspend if created on
+    /// `ClockBoottimeAlarm`, but does not wake the system.
+    NonWaking,
+}
+
+/// A [TimerFd] on `ClockRealtimeAlarm` or `ClockBoottimeAlarm` that wakes
+/// the system from suspend when it expires.
+#[derive(Debug)]
+pub struct AlarmTimer {
+    timer: TimerFd,
+    wakes_system: bool,
+}
+
+impl AlarmTimer {
+    /// Creates an alarm timer on `ClockRealtimeAlarm` or `ClockBoottimeAlarm`.
+    /// Other clocks are rejected with `EINVAL`.
+    ///
+    /// Alarm timers require `CAP_WAKE_ALARM` and an RTC that supports
+    /// alarms. Without either, `fallback` decides whether to fail with
+    /// `EPERM`, respectively `ENODEV` or `EOPNOTSUPP`, or to use the
+    /// non-alarm clock.
+    pub fn new(
+        clockid: ClockId,
+        flags: TimerFdFlags,
+        fallback: AlarmFallback,
+    ) -> Result<Self, Errno> {
+        let fallback_clock = match clockid {
+            ClockId::ClockRealtimeAlarm => ClockId::ClockRealtime,
+            ClockId::ClockBoottimeAlarm => ClockId::ClockBoottime,
+            _ => return Err(Errno::EINVAL),
+        };
+        let err = if has_effective(CAP_WAKE_ALARM)? {
+            match TimerFd::new(clockid, flags) {
+                Ok(timer) => {
+                    return Ok(Self {
+                        timer,
+                        wakes_system: true,
+                    })
+                }
+                // Older kernels report a missing RTC with ENODEV.
+                Err(err @ (Errno::ENODEV | Errno::EOPNOTSUPP)) => err,
+                Err(err) => return Err(err),
+            }
+        } else {
+            Errno::EPERM
+        };
+        match fallback {
+            AlarmFallback::Fail => Err(err),
+            AlarmFallback::NonWaking => Ok(Self {
+                timer: TimerFd::new(fallback_clock, flags)?,
+                wakes_system: false,
+            }),
+        }
+    }
+
+    /// Returns whether the timer wakes the system from suspend, i.e.
+    /// whether it was created on an alarm clock.
+    pub fn wakes_system(&self) -> bool {
+        self.wakes_system
+    }
+
+    /// Returns the underlying [TimerFd] to set, query and wait on the timer.
+    pub fn timer(&self) -> &TimerFd {
+        &self.timer
+    }
+}
+
+impl AsFd for AlarmTimer {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.timer.as_fd()
+    }
+}
+
+impl AsRawFd for AlarmTimer {
+    fn as_raw_fd(&self) -> RawFd {
+        self.timer.as_raw_fd()
+    }
+}
+
+impl From<AlarmTimer> for TimerFd {
+    fn from(alarm: AlarmTimer) -> Self {
+        alarm.timer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{get_time, set_time};
+
+    #[test]
+    fn test_relative() {
+        let timer = TimerFd::new(ClockId::ClockMonotonic, TimerFdFlags::TFD_CLOEXEC).unwrap();
+        timer
+            .set_relative(TimeSpec::nanoseconds(1_000_000), TimeSpec::zeroed())
+            .unwrap();
+        assert_eq!(timer.wait().unwrap(), Expiration::Expired(1));
+        assert_eq!(timer.get().unwrap(), ItimerSpec::default());
+    }
+
+    #[test]
+    fn test_absolute_periodic() {
+        let timer = TimerFd::new(ClockId::ClockMonotonic, TimerFdFlags::empty()).unwrap();
+        let now = get_time(ClockId::ClockMonotonic).unwrap();
+        let interval = TimeSpec::nanoseconds(500_000);
+        timer
+            .set_absolute(now + TimeSpec::nanoseconds(1_000_000), interval, false)
+            .unwrap();
+        let Expiration::Expired(count) = timer.wait().unwrap() else {
+            panic!("unexpected clock set");
+        };
+        assert!(count >= 1);
+        assert_eq!(timer.get().unwrap().it_interval, interval);
+        timer.disarm().unwrap();
+        assert_eq!(timer.get().unwrap(), ItimerSpec::default());
+    }
+
+    #[test]
+    fn test_cancel_on_set() {
+        let timer = TimerFd::new(ClockId::ClockRealtime, TimerFdFlags::empty()).unwrap();
+        let now = get_time(ClockId::ClockRealtime).unwrap();
+        timer
+            .set_absolute(now + TimeSpec::seconds(60), TimeSpec::zeroed(), true)
+            .unwrap();
+        assert!(timer.get().unwrap().it_value > TimeSpec::zeroed());
+
+        // Setting the clock to its current value is a discontinuous change.
+        match set_time(
+            ClockId::ClockRealtime,
+            get_time(ClockId::ClockRealtime).unwrap(),
+        ) {
+            Err(Errno::EPERM) => {
+                eprintln!("test_cancel_on_set skipped: clock_settime requires CAP_SYS_TIME")
+            }
+            ret => {
+                ret.unwrap();
+                assert_eq!(timer.wait().unwrap(), Expiration::ClockSet);
+            }
+        }
+    }
+
+    #[test]
+    fn test_alarm() {
+        assert_eq!(
+            AlarmTimer::new(
+                ClockId::ClockMonotonic,
+                TimerFdFlags::empty(),
+                AlarmFallback::Fail
+            )
+            .unwrap_err(),
+            Errno::EINVAL
+        );
+
+        let alarm = AlarmTimer::new(
+            ClockId::ClockBoottimeAlarm,
+            TimerFdFlags::empty(),
+            AlarmFallback::NonWaking,
+        )
+        .unwrap();
+        let expected_clock = if alarm.wakes_system() {
+            ClockId::ClockBoottimeAlarm
+        } else {
+            ClockId::ClockBoottime
+        };
+        assert_eq!(alarm.timer().clock_id(), expected_clock);
+        alarm
+            .timer()
+            .set_relative(TimeSpec::nanoseconds(1_000_000), TimeSpec::zeroed())
+            .unwrap();
+        assert_eq!(alarm.timer().wait().unwrap(), Expiration::Expired(1));
+
+        let strict = AlarmTimer::new(
+            ClockId::ClockRealtimeAlarm,
+            TimerFdFlags::empty(),
+            AlarmFallback::Fail,
+        );
+        assert_eq!(strict.is_ok(), alarm.wakes_system());
+    }
+
+    #[test]
+    fn test_nonblock() {
+        let timer = TimerFd::new(ClockId::ClockBoottime, TimerFdFlags::TFD_NONBLOCK).unwrap();
+        assert_eq!(timer.wait(), Err(Errno::EAGAIN));
+        assert!(timer.as_raw_fd() >= 0);
+    }
+}